@@ -124,6 +124,145 @@ pub struct ClaimArgs {
     pub sub_private: Option<String>,
 }
 
+#[derive(Parser, Debug)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommand,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum ConfigCommand {
+    #[command(about = "Show the resolved settings and where each one comes from.")]
+    Show(ConfigShowArgs),
+}
+
+#[derive(Parser, Clone, Debug)]
+pub struct ConfigShowArgs {}
+
+#[derive(Parser, Debug)]
+pub struct EstimateArgs {
+    #[arg(
+        long,
+        short,
+        value_name = "THREAD_COUNT",
+        help = "The number of cores to benchmark and estimate rewards for.",
+        default_value = "1"
+    )]
+    pub cores: String,
+
+    #[arg(
+        long,
+        value_name = "HASHES_PER_SEC",
+        help = "Estimate for this hashpower instead of benchmarking.",
+        conflicts_with = "cores"
+    )]
+    pub hashrate: Option<u64>,
+
+    #[arg(
+        long,
+        short,
+        value_name = "SECONDS",
+        help = "The number seconds before the deadline to stop collecting and start submitting.",
+        default_value = "5"
+    )]
+    pub buffer_time: u64,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of accounts collecting with this hashpower each.",
+        default_value = "1"
+    )]
+    pub accounts: u64,
+}
+
+#[derive(Parser, Debug)]
+pub struct FeesArgs {
+    #[command(subcommand)]
+    pub command: FeesCommand,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum FeesCommand {
+    #[command(about = "Show recorded dynamic priority fee estimates.")]
+    History(FeesHistoryArgs),
+
+    #[command(about = "Show fees paid for recently landed transactions.")]
+    Landed(FeesHistoryArgs),
+}
+
+#[derive(Parser, Clone, Debug)]
+pub struct FeesHistoryArgs {
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Number of most recent estimates to show.",
+        default_value = "20"
+    )]
+    pub limit: usize,
+}
+
+#[derive(Parser, Debug)]
+pub struct FundArgs {
+    #[arg(
+        long,
+        value_name = "ETH",
+        help = "Top up sub-wallets whose ETH balance is below this amount.",
+        default_value = "0.0005"
+    )]
+    pub min: f64,
+
+    #[arg(
+        long,
+        value_name = "ETH",
+        help = "The ETH balance to top each sub-wallet up to.",
+        default_value = "0.001"
+    )]
+    pub top_up_to: f64,
+
+    #[arg(
+        long,
+        help = "Preview the transfers without sending any transactions.",
+        default_value = "false"
+    )]
+    pub dry_run: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct HistoryArgs {
+    #[arg(
+        long,
+        short,
+        value_name = "ACCOUNT_ADDRESS",
+        help = "The authority to fetch the mining history of. Defaults to the signer. Use the pool address for pool history."
+    )]
+    pub authority: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DATE",
+        help = "Only include transactions on or after this date (YYYY-MM-DD). Defaults to the last 7 days."
+    )]
+    pub since: Option<String>,
+
+    #[arg(long, value_name = "FORMAT", help = "Export the daily history instead of printing a table.")]
+    pub export: Option<ExportFormat>,
+
+    #[arg(
+        short = 'o',
+        long,
+        value_name = "FILEPATH",
+        help = "File to write the export to. Defaults to stdout."
+    )]
+    pub file: Option<String>,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
 #[cfg(feature = "admin")]
 #[derive(Parser, Debug)]
 pub struct InitializeArgs {}
@@ -160,9 +299,48 @@ pub struct CollectArgs {
         long,
         short,
         value_name = "POOL_URL",
-        help = "The optional pool url to join and forward solutions to."
+        value_delimiter = ',',
+        help = "The optional pool urls to join and forward solutions to, in order of priority. Collecting fails over to the next pool and back to the first when it recovers. Use `auto` for the best pools by `pool list --probe`."
     )]
-    pub pool_url: Option<String>,
+    pub pool_url: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Consecutive failures or stale challenges before switching to the next pool.",
+        default_value = "3"
+    )]
+    pub pool_failures: u32,
+
+    #[arg(
+        long,
+        requires = "pool_url",
+        help = "Collect against `bitz pool mock` without a chain. Registering skips the onchain member account and the cutoff follows the local clock.",
+        default_value = "false"
+    )]
+    pub pool_mock: bool,
+
+    #[arg(
+        long,
+        value_name = "AMOUNT",
+        help = "Commit the pool balance to the chain between rounds once more than this many BITZ are pending."
+    )]
+    pub auto_commit_above: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "AMOUNT",
+        help = "Claim the onchain pool balance between rounds once it exceeds this many BITZ."
+    )]
+    pub auto_claim_above: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "WALLET_ADDRESS",
+        requires = "auto_claim_above",
+        help = "Wallet address to receive automatically claimed tokens. Defaults to the signer."
+    )]
+    pub to: Option<String>,
 
     #[arg(
         long,
@@ -186,11 +364,137 @@ pub struct PoolArgs {
 pub enum PoolCommand {
     #[command(about = "Commit a pending pool balance to the chain.")]
     Commit(PoolCommitArgs),
+
+    #[command(about = "List the pools onchain.")]
+    List(PoolListArgs),
+
+    #[command(about = "Withdraw boost tokens staked in the pool before staking moved to the global boost.")]
+    Unstake(UnstakeArgs),
+
+    #[command(about = "List your pool shares per pool and mint.")]
+    Shares(PoolSharesArgs),
+
+    #[command(about = "Reconcile the balance a pool reports against the chain and the local ledger.")]
+    Audit(PoolAuditArgs),
+
+    #[command(about = "Run a local mock pool server for testing the pool client offline.")]
+    Mock(PoolMockArgs),
+
+    #[command(about = "Operate the pool of the signer, serving the pool API to its members.")]
+    Serve(PoolServeArgs),
 }
 
 #[derive(Parser, Clone, Debug)]
 pub struct PoolCommitArgs {}
 
+#[derive(Parser, Clone, Debug)]
+pub struct PoolAuditArgs {
+    #[arg(
+        value_name = "POOL_URL",
+        help = "The pool url to audit. Defaults to the pool url given before the subcommand."
+    )]
+    pub pool_url: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "AMOUNT",
+        help = "The largest gap in BITZ that is not flagged.",
+        default_value = "0.00001"
+    )]
+    pub tolerance: f64,
+}
+
+#[derive(Parser, Clone, Debug)]
+pub struct PoolSharesArgs {
+    #[arg(
+        long,
+        short,
+        value_name = "ACCOUNT_ADDRESS",
+        help = "List the pool shares of another authority."
+    )]
+    pub authority: Option<String>,
+}
+
+#[derive(Parser, Clone, Debug)]
+pub struct PoolListArgs {
+    #[arg(
+        long,
+        help = "Contact each pool to measure latency, verify its address and rank it by recent rewards per hash.",
+        default_value = "false"
+    )]
+    pub probe: bool,
+}
+
+#[derive(Parser, Clone, Debug)]
+pub struct PoolMockArgs {
+    #[arg(
+        long,
+        value_name = "PORT",
+        help = "The port to listen on.",
+        default_value = "8787"
+    )]
+    pub port: u16,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "The pool address to advertise, e.g. a pool created on a local validator. Defaults to the pool of a random authority."
+    )]
+    pub address: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DIFFICULTY",
+        help = "The minimum difficulty of contributed solutions.",
+        default_value = "8"
+    )]
+    pub min_difficulty: u64,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "The number of seconds between new challenges.",
+        default_value = "60"
+    )]
+    pub round_time: u64,
+}
+
+#[derive(Parser, Clone, Debug)]
+pub struct PoolServeArgs {
+    #[arg(
+        long,
+        value_name = "PORT",
+        help = "The port to listen on.",
+        default_value = "8080"
+    )]
+    pub port: u16,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "The file to persist member balances in.",
+        default_value = "pool-ledger.json"
+    )]
+    pub ledger: String,
+
+    #[arg(
+        long,
+        short,
+        value_name = "SECONDS",
+        help = "The number seconds before the deadline to stop accepting contributions and submit the best one.",
+        default_value = "5"
+    )]
+    pub buffer_time: u64,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "The number of seconds between onchain attributions of member balances.",
+        default_value = "600"
+    )]
+    pub attribute_interval: u64,
+}
+
 #[derive(Parser, Debug)]
 pub struct ProgramArgs {}
 
@@ -304,7 +608,7 @@ pub struct TransferArgs {
     pub to: String,
 }
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Clone, Debug)]
 pub struct UnstakeArgs {
     #[arg(
         value_name = "AMOUNT",
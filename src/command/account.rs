@@ -2,6 +2,7 @@ use std::str::FromStr;
 
 use colored::Colorize;
 use eore_api::state::proof_pda;
+use serde::Serialize;
 use solana_program::{native_token::lamports_to_sol, pubkey::Pubkey};
 use solana_sdk::signature::Signer;
 use spl_token::amount_to_ui_amount;
@@ -17,11 +18,23 @@ use crate::{
     args::{AccountArgs, AccountCloseArgs, AccountCommand, ClaimArgs},
     utils::{
         amount_u64_to_f64, ask_confirm, format_timestamp, get_proof, get_proof_with_authority,
-        ComputeBudget, TableData, TableSectionTitle,
+        print_json, ComputeBudget, OutputFormat, ProofOutput, TableData, TableSectionTitle,
+        TokenAmount,
     },
     Miner,
 };
 
+/// JSON output of `account`.
+#[derive(Serialize)]
+struct AccountOutput {
+    address: String,
+    balance: TokenAmount,
+    eth: TokenAmount,
+    proof_address: String,
+    /// `null` when the account has no proof.
+    proof: Option<ProofOutput>,
+}
+
 impl Miner {
     pub async fn account(&self, args: AccountArgs) {
         if let Some(command) = args.command {
@@ -50,19 +63,8 @@ impl Miner {
         };
 
         // Aggregate data
-        let mut data = vec![];
-        self.get_account_data(address, &mut data).await;
-        self.get_proof_data(address, &mut data).await;
-
-        // Build table
-        let mut table = Table::new(data);
-        table.with(Remove::row(Rows::first()));
-        table.modify(Columns::single(1), Alignment::right());
-        table.with(Style::blank());
-        table.section_title(0, "Account");
-        table.section_title(3, "Proof");
-
-        println!("{table}\n");
+        let account = self.get_account_output(address).await;
+        self.print_account(&account);
     }
 
     async fn get_proof_account(&self, args: AccountArgs) {
@@ -82,22 +84,11 @@ impl Miner {
         let proof = get_proof(&self.rpc_client, proof_address)
             .await
             .expect("Failed to fetch proof account");
-        let mut data = vec![];
-        self.get_account_data(proof.authority, &mut data).await;
-        self.get_proof_data(proof.authority, &mut data).await;
-
-        // Build table
-        let mut table = Table::new(data);
-        table.with(Remove::row(Rows::first()));
-        table.modify(Columns::single(1), Alignment::right());
-        table.with(Style::blank());
-        table.section_title(0, "Account");
-        table.section_title(3, "Proof");
-
-        println!("{table}\n");
+        let account = self.get_account_output(proof.authority).await;
+        self.print_account(&account);
     }
 
-    async fn get_account_data(&self, authority: Pubkey, data: &mut Vec<TableData>) {
+    async fn get_account_output(&self, authority: Pubkey) -> AccountOutput {
         // Get BITZ balance
         let token_account_address = spl_associated_token_account::get_associated_token_address(
             &authority,
@@ -108,9 +99,9 @@ impl Miner {
             .get_token_account(&token_account_address)
             .await
         {
-            token_account.token_amount.ui_amount_string
+            token_account.token_amount.amount.parse().unwrap_or(0)
         } else {
-            "0".to_string()
+            0
         };
 
         // Get ETH balance
@@ -120,50 +111,69 @@ impl Miner {
             .await
             .expect("Failed to fetch ETH balance");
 
-        // Aggregate data
+        // Get proof
+        let proof_address = proof_pda(authority).0;
+        let proof = get_proof(&self.rpc_client, proof_address)
+            .await
+            .ok()
+            .map(ProofOutput::from);
+
+        AccountOutput {
+            address: authority.to_string(),
+            balance: TokenAmount::bitz(token_balance),
+            eth: TokenAmount::eth(sol_balance),
+            proof_address: proof_address.to_string(),
+            proof,
+        }
+    }
+
+    fn print_account(&self, account: &AccountOutput) {
+        if self.output == OutputFormat::Json {
+            return print_json(account);
+        }
+
+        // Account
+        let mut data = vec![];
         data.push(TableData {
             key: "Address".to_string(),
-            value: authority.to_string(),
+            value: account.address.clone(),
         });
         data.push(TableData {
             key: "Balance".to_string(),
-            value: format!("{} BITZ", token_balance),
+            value: format!(
+                "{} BITZ",
+                amount_to_ui_amount(account.balance.amount, account.balance.decimals)
+            ),
         });
         data.push(TableData {
             key: "ETH".to_string(),
-            value: format!("{} ETH", lamports_to_sol(sol_balance)),
+            value: format!("{} ETH", lamports_to_sol(account.eth.amount)),
         });
-    }
-
-    async fn get_proof_data(&self, authority: Pubkey, data: &mut Vec<TableData>) {
-        // Parse addresses
-        let proof_address = proof_pda(authority).0;
-        let proof = get_proof(&self.rpc_client, proof_address).await;
 
-        // Aggregate data
+        // Proof
         data.push(TableData {
             key: "Address".to_string(),
-            value: proof_address.to_string(),
+            value: account.proof_address.clone(),
         });
-        if let Ok(proof) = proof {
+        if let Some(proof) = &account.proof {
             data.push(TableData {
                 key: "Authority".to_string(),
-                value: authority.to_string(),
+                value: proof.authority.clone(),
             });
             data.push(TableData {
                 key: "Balance".to_string(),
-                value: if proof.balance > 0 {
-                    format!("{} BITZ", amount_u64_to_f64(proof.balance))
+                value: if proof.balance.amount > 0 {
+                    format!("{} BITZ", amount_u64_to_f64(proof.balance.amount))
                         .bold()
                         .yellow()
                         .to_string()
                 } else {
-                    format!("{} BITZ", amount_u64_to_f64(proof.balance))
+                    format!("{} BITZ", amount_u64_to_f64(proof.balance.amount))
                 },
             });
             data.push(TableData {
                 key: "Last hash".to_string(),
-                value: solana_sdk::hash::Hash::new_from_array(proof.last_hash).to_string(),
+                value: proof.last_hash.clone(),
             });
             data.push(TableData {
                 key: "Last hash at".to_string(),
@@ -177,12 +187,12 @@ impl Miner {
                 key: "Lifetime rewards".to_string(),
                 value: format!(
                     "{} BITZ",
-                    amount_to_ui_amount(proof.total_rewards, eore_api::consts::TOKEN_DECIMALS)
+                    amount_to_ui_amount(proof.total_rewards.amount, proof.total_rewards.decimals)
                 ),
             });
             data.push(TableData {
                 key: "Miner".to_string(),
-                value: proof.miner.to_string(),
+                value: proof.miner.clone(),
             });
         } else {
             data.push(TableData {
@@ -190,6 +200,16 @@ impl Miner {
                 value: "Not found".red().bold().to_string(),
             });
         }
+
+        // Build table
+        let mut table = Table::new(data);
+        table.with(Remove::row(Rows::first()));
+        table.modify(Columns::single(1), Alignment::right());
+        table.with(Style::blank());
+        table.section_title(0, "Account");
+        table.section_title(3, "Proof");
+
+        println!("{table}\n");
     }
 
     async fn close(&self, _args: AccountCloseArgs) {
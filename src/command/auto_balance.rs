@@ -0,0 +1,112 @@
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use colored::*;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    args::{ClaimArgs, CollectArgs},
+    error::Error,
+    utils::{amount_f64_to_u64, amount_u64_to_string},
+    Miner,
+};
+
+use super::pool::Pool;
+
+/// Commits and claims the pool balance in the background once it crosses the
+/// thresholds of `--auto-commit-above` and `--auto-claim-above`. At most one
+/// check runs at a time, so a slow transaction never piles up behind rounds.
+#[derive(Clone)]
+pub struct AutoBalance {
+    commit_above: Option<u64>,
+    claim_above: Option<u64>,
+    to: Option<String>,
+    busy: Arc<AtomicBool>,
+}
+
+impl AutoBalance {
+    /// Returns `None` unless a threshold is set.
+    pub fn new(args: &CollectArgs) -> Result<Option<Self>, Error> {
+        if args.auto_commit_above.is_none() && args.auto_claim_above.is_none() {
+            return Ok(None);
+        }
+        if let Some(to) = &args.to {
+            Pubkey::from_str(to)?;
+        }
+        Ok(Some(Self {
+            commit_above: args.auto_commit_above.map(amount_f64_to_u64),
+            claim_above: args.auto_claim_above.map(amount_f64_to_u64),
+            to: args.to.clone(),
+            busy: Arc::new(AtomicBool::new(false)),
+        }))
+    }
+
+    /// Checks the balance with the given pool, unless the previous check is
+    /// still running.
+    pub fn check(&self, miner: &Miner, pool: &Pool) {
+        if self.busy.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let auto_balance = self.clone();
+        let miner = miner.clone();
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            if let Err(err) = auto_balance.run(&miner, &pool).await {
+                println!(
+                    "{} Automatic commit or claim with {} failed: {}",
+                    "WARNING".bold().yellow(),
+                    pool.label(),
+                    err
+                );
+            }
+            auto_balance.busy.store(false, Ordering::Release);
+        });
+    }
+
+    async fn run(&self, miner: &Miner, pool: &Pool) -> Result<(), Error> {
+        let pool_address = pool.get_pool_address().await?.address;
+        let onchain = pool.get_pool_member_onchain(miner, pool_address).await?;
+
+        // Commit the balance the pool has not attributed yet
+        if let Some(commit_above) = self.commit_above {
+            let member = pool.get_pool_member(miner).await?;
+            let total_balance = member.total_balance.max(0) as u64;
+            let pending = total_balance.saturating_sub(onchain.total_balance);
+            if pending > commit_above {
+                println!(
+                    "Committing {} BITZ pending with {}",
+                    amount_u64_to_string(pending),
+                    pool.label()
+                );
+                pool.post_update_balance(miner).await?;
+
+                // The pool lands the commit, so the balance is claimed in a later check
+                return Ok(());
+            }
+        }
+
+        // Claim the attributed balance
+        if let Some(claim_above) = self.claim_above {
+            if onchain.balance > claim_above {
+                println!(
+                    "Claiming {} BITZ from {}",
+                    amount_u64_to_string(onchain.balance),
+                    pool.label()
+                );
+                let args = ClaimArgs {
+                    amount: None,
+                    to: self.to.clone(),
+                    pool_url: Some(pool.pool_url.clone()),
+                    sub_private: None,
+                };
+                miner.claim_from_pool(args, pool).await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -3,6 +3,8 @@ use std::io::Write;
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_rpc_client::rpc_client::RpcClient;
 
+use clap::ValueEnum;
+
 use crate::{
     args::CollectArgs,
     error::Error,
@@ -45,6 +47,11 @@ impl Miner {
                 let args_cores = args.cores.clone();
                 let args_buffer_time = args.buffer_time;
                 let args_device_id = args.device_id;
+                let args_pool_failures = args.pool_failures;
+                let args_pool_mock = args.pool_mock;
+                let args_auto_commit_above = args.auto_commit_above;
+                let args_auto_claim_above = args.auto_claim_above;
+                let args_to = args.to.clone();
                 let args_verbose = args.verbose;
                 
                 // 使用std::process::Command来启动新进程，避免异步问题
@@ -54,17 +61,52 @@ impl Miner {
                 cmd.arg("collect")
                    .arg("--cores").arg(&args_cores)
                    .arg("--buffer-time").arg(args_buffer_time.to_string())
-                   .arg("--private-key").arg(private_key);
+                   .arg("--private-key").arg(private_key)
+                   // 设置已全部显式传入，子进程不再读取bitz.toml（避免配置中的sub-private导致递归批量启动）
+                   .arg("--no-profile");
+                
+                // 同理清除继承的BITZ_*环境变量（BITZ_SUB_PRIVATE同样会导致递归批量启动）
+                for (key, _) in std::env::vars_os() {
+                    if key.to_string_lossy().starts_with("BITZ_") {
+                        cmd.env_remove(key);
+                    }
+                }
                 
-                // 添加RPC URL
-                cmd.arg("--rpc").arg(self.rpc_client.url());
+                // 添加RPC URL（保留多节点和角色配置）
+                for endpoint in &self.rpc_endpoints {
+                    cmd.arg("--rpc").arg(endpoint);
+                }
                 
-                // 添加矿池URL（如果有）
-                if let Some(url) = pool_url {
-                    cmd.arg("--pool-url").arg(url);
+                // 添加websocket订阅配置
+                match &self.ws_url {
+                    Some(ws_url) => {
+                        cmd.arg("--ws-url").arg(ws_url);
+                    }
+                    None => {
+                        cmd.arg("--no-ws");
+                    }
+                }
+                
+                // 添加矿池URL（如果有，按优先级保留全部备用矿池）
+                if !pool_url.is_empty() {
+                    cmd.arg("--pool-url").arg(pool_url.join(","))
+                       .arg("--pool-failures").arg(args_pool_failures.to_string());
                     if let Some(device_id) = args_device_id {
                         cmd.arg("--device-id").arg(device_id.to_string());
                     }
+                    if args_pool_mock {
+                        cmd.arg("--pool-mock");
+                    }
+                    // 自动提交和领取矿池余额
+                    if let Some(amount) = args_auto_commit_above {
+                        cmd.arg("--auto-commit-above").arg(amount.to_string());
+                    }
+                    if let Some(amount) = args_auto_claim_above {
+                        cmd.arg("--auto-claim-above").arg(amount.to_string());
+                        if let Some(to) = &args_to {
+                            cmd.arg("--to").arg(to);
+                        }
+                    }
                 }
                 
                 // 添加priority fee
@@ -77,18 +119,49 @@ impl Miner {
                     cmd.arg("--fee-private-key").arg(fee_key);
                 }
                 
+                // 添加计算单元余量
+                cmd.arg("--cu-margin").arg(self.compute_unit_margin.to_string());
+                
+                // 添加交易重试策略
+                cmd.arg("--send-attempts").arg(self.send_policy.max_attempts.to_string())
+                   .arg("--send-timeout").arg(self.send_policy.deadline.as_secs().to_string())
+                   .arg("--send-backoff").arg(self.send_policy.backoff.as_millis().to_string());
+                
+                // 添加手续费支出限制
+                cmd.arg("--min-balance").arg(self.spend_limits.min_balance.to_string());
+                // 子进程共用同一个fee payer且各自在内存中累计手续费，因此平分每日额度，每个子进程只统计自己的手续费
+                if let Some(max_fee_per_day) = self.spend_limits.max_fee_per_day {
+                    cmd.arg("--max-fee-per-day").arg((max_fee_per_day / private_keys.len() as f64).to_string())
+                       .arg("--fee-budget-per-signer");
+                }
+                if let Some(bitz_price) = self.spend_limits.bitz_price {
+                    cmd.arg("--bitz-price").arg(bitz_price.to_string());
+                }
+                
                 // 添加verbose模式
                 if args_verbose {
                     cmd.arg("--verbose");
                 }
                 
-                // 添加dynamic fee相关参数
+                // 添加优先费相关参数（不论是否启用dynamic fee都传入）
                 if dynamic_fee {
                     cmd.arg("--dynamic-fee");
-                    if let Some(url) = &dynamic_fee_url {
-                        cmd.arg("--dynamic-fee-url").arg(url);
-                    }
                 }
+                if let Some(url) = &dynamic_fee_url {
+                    cmd.arg("--dynamic-fee-url").arg(url);
+                }
+                let fee_config = self.fee_config;
+                cmd.arg("--fee-strategy").arg(value_name(fee_config.strategy))
+                   .arg("--fee-percentile").arg(fee_config.percentile.to_string())
+                   .arg("--fee-smoothing").arg(fee_config.smoothing.to_string())
+                   .arg("--fee-floor").arg(fee_config.floor.to_string())
+                   .arg("--fee-escalation").arg(fee_config.escalation.to_string());
+                
+                // 添加模拟模式和输出格式（模拟模式下子进程同样不广播交易）
+                if self.simulate {
+                    cmd.arg("--simulate");
+                }
+                cmd.arg("--output").arg(value_name(self.output));
                 
                 // 为每个进程创建日志文件
                 let log_dir = std::path::Path::new("logs");
@@ -135,4 +208,12 @@ impl Miner {
             Err(Error::Internal("未指定批量挖矿私钥文件".to_string()))
         }
     }
-} 
\ No newline at end of file
+} 
+
+/// 参数值在命令行上的写法
+fn value_name<T: ValueEnum>(value: T) -> String {
+    value
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
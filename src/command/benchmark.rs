@@ -15,63 +15,12 @@ impl Miner {
         self.benchmark_check_cores(cores);
 
         // Dispatch job to each thread
-        let challenge = [0; 32];
         let progress_bar = Arc::new(spinner::new_progress_bar());
         progress_bar.set_message(format!(
             "Benchmarking. This will take {} sec...",
             TEST_DURATION
         ));
-        let core_ids = core_affinity::get_core_ids().expect("Failed to fetch core count");
-        let handles: Vec<_> = core_ids
-            .into_iter()
-            .map(|i| {
-                std::thread::spawn({
-                    move || {
-                        let timer = Instant::now();
-                        let first_nonce = u64::MAX
-                            .saturating_div(cores)
-                            .saturating_mul(i.id as u64);
-                        let mut nonce = first_nonce;
-                        let mut memory = equix::SolverMemory::new();
-                        loop {
-                            // Return if core should not be used
-                            if (i.id as u64).ge(&cores) {
-                                return 0;
-                            }
-
-                            // Pin to core
-                            let _ = core_affinity::set_for_current(i);
-
-                            // Create hash
-                            let _hx = drillx::hash_with_memory(
-                                &mut memory,
-                                &challenge,
-                                &nonce.to_le_bytes(),
-                            );
-
-                            // Increment nonce
-                            nonce += 1;
-
-                            // Exit if time has elapsed
-                            if (timer.elapsed().as_secs() as i64).ge(&TEST_DURATION) {
-                                break;
-                            }
-                        }
-
-                        // Return hash count
-                        nonce - first_nonce
-                    }
-                })
-            })
-            .collect();
-
-        // Join handles and return best nonce
-        let mut total_nonces = 0;
-        for h in handles {
-            if let Ok(count) = h.join() {
-                total_nonces += count;
-            }
-        }
+        let total_nonces = hash_for(cores, TEST_DURATION);
 
         // Update log
         progress_bar.finish_with_message(format!(
@@ -80,7 +29,7 @@ impl Miner {
         ));
     }
     
-    fn benchmark_parse_cores(&self, cores: &str) -> u64 {
+    pub fn benchmark_parse_cores(&self, cores: &str) -> u64 {
         if cores == "ALL" {
             num_cpus::get() as u64
         } else {
@@ -88,7 +37,7 @@ impl Miner {
         }
     }
 
-    fn benchmark_check_cores(&self, cores: u64) {
+    pub fn benchmark_check_cores(&self, cores: u64) {
         let num_cores = num_cpus::get() as u64;
         if cores.gt(&num_cores) {
             println!(
@@ -99,3 +48,56 @@ impl Miner {
         }
     }
 }
+
+/// Hashes on the given number of cores for `duration` seconds and returns the
+/// total number of hashes.
+pub fn hash_for(cores: u64, duration: i64) -> u64 {
+    let challenge = [0; 32];
+    let core_ids = core_affinity::get_core_ids().expect("Failed to fetch core count");
+    let handles: Vec<_> = core_ids
+        .into_iter()
+        .map(|i| {
+            std::thread::spawn({
+                move || {
+                    let timer = Instant::now();
+                    let first_nonce = u64::MAX.saturating_div(cores).saturating_mul(i.id as u64);
+                    let mut nonce = first_nonce;
+                    let mut memory = equix::SolverMemory::new();
+                    loop {
+                        // Return if core should not be used
+                        if (i.id as u64).ge(&cores) {
+                            return 0;
+                        }
+
+                        // Pin to core
+                        let _ = core_affinity::set_for_current(i);
+
+                        // Create hash
+                        let _hx =
+                            drillx::hash_with_memory(&mut memory, &challenge, &nonce.to_le_bytes());
+
+                        // Increment nonce
+                        nonce += 1;
+
+                        // Exit if time has elapsed
+                        if (timer.elapsed().as_secs() as i64).ge(&duration) {
+                            break;
+                        }
+                    }
+
+                    // Return hash count
+                    nonce - first_nonce
+                }
+            })
+        })
+        .collect();
+
+    // Join handles and return best nonce
+    let mut total_nonces = 0;
+    for h in handles {
+        if let Ok(count) = h.join() {
+            total_nonces += count;
+        }
+    }
+    total_nonces
+}
@@ -1,4 +1,5 @@
 use colored::*;
+use serde::Serialize;
 use solana_sdk::signature::{Keypair, Signer};
 use tabled::{
     settings::{Alignment, Style},
@@ -6,7 +7,10 @@ use tabled::{
 };
 
 use crate::{args::CheckArgs, Miner};
-use crate::utils::{amount_u64_to_f64, format_timestamp, get_proof_with_authority};
+use crate::utils::{
+    amount_u64_to_f64, format_timestamp, get_proof_with_authority, print_json, OutputFormat,
+    TokenAmount,
+};
 
 #[derive(Tabled)]
 struct AccountData {
@@ -18,6 +22,17 @@ struct AccountData {
     last_hash_at: String,
 }
 
+/// JSON output of `check`, one per account. `balance` and `last_hash_at` are
+/// `null` and `error` is set when the proof could not be fetched.
+#[derive(Serialize)]
+struct CheckOutput {
+    address: String,
+    balance: Option<TokenAmount>,
+    /// Unix timestamp, 0 when the account has never mined.
+    last_hash_at: Option<i64>,
+    error: Option<String>,
+}
+
 impl Miner {
     pub async fn check(&self, args: CheckArgs) {
         let filepath = if let Some(path) = args.sub_private {
@@ -51,16 +66,21 @@ impl Miner {
             return;
         }
 
-        println!("正在查询 {} 个账户的余额和挖矿时间...", private_keys.len());
+        // JSON 模式下只有结果写入 stdout
+        let json = self.output == OutputFormat::Json;
+        if !json {
+            println!("正在查询 {} 个账户的余额和挖矿时间...", private_keys.len());
+        }
 
         let mut account_data = Vec::new();
+        let mut output = Vec::new();
 
         // 查询每个账户的信息
         for private_key in private_keys {
             let bytes = match bs58::decode(&private_key).into_vec() {
                 Ok(b) => b,
                 Err(_) => {
-                    println!("{}", format!("错误: 私钥格式错误: {}", private_key).red());
+                    eprintln!("{}", format!("错误: 私钥格式错误: {}", private_key).red());
                     continue;
                 }
             };
@@ -68,7 +88,7 @@ impl Miner {
             let keypair = match Keypair::from_bytes(&bytes) {
                 Ok(k) => k,
                 Err(_) => {
-                    println!("{}", "错误: 无法从私钥创建密钥对".red());
+                    eprintln!("{}", "错误: 无法从私钥创建密钥对".red());
                     continue;
                 }
             };
@@ -79,6 +99,12 @@ impl Miner {
             // 获取账户证明数据
             match get_proof_with_authority(&self.rpc_client, pubkey).await {
                 Ok(proof) => {
+                    output.push(CheckOutput {
+                        address: address.clone(),
+                        balance: Some(TokenAmount::bitz(proof.balance)),
+                        last_hash_at: Some(proof.last_hash_at),
+                        error: None,
+                    });
                     let balance = format!("{:.10} BITZ", amount_u64_to_f64(proof.balance));
                     let last_hash_at = if proof.last_hash_at > 0 {
                         format_timestamp(proof.last_hash_at)
@@ -93,16 +119,27 @@ impl Miner {
                     });
                 }
                 Err(e) => {
+                    output.push(CheckOutput {
+                        address: address.clone(),
+                        balance: None,
+                        last_hash_at: None,
+                        error: Some(e.to_string()),
+                    });
                     account_data.push(AccountData {
                         address: format!("{}...{}", &address[0..4], &address[address.len() - 4..]),
                         balance: "无法获取".to_string(),
                         last_hash_at: "无法获取".to_string(),
                     });
-                    println!("{}", format!("警告: 账户 {} 查询失败: {:?}", address, e).yellow());
+                    eprintln!("{}", format!("警告: 账户 {} 查询失败: {:?}", address, e).yellow());
                 }
             }
         }
 
+        if json {
+            print_json(&output);
+            return;
+        }
+
         // 显示表格
         if account_data.is_empty() {
             println!("{}", "未能成功查询任何账户信息。".red());
@@ -121,12 +121,22 @@ impl Miner {
                         priority_fee: self.priority_fee,
                         dynamic_fee_url: self.dynamic_fee_url.clone(),
                         dynamic_fee: self.dynamic_fee,
+                        fee_config: self.fee_config,
+                        fee_ema: self.fee_ema.clone(),
+                        compute_unit_margin: self.compute_unit_margin,
+                        simulate: self.simulate,
+                        send_policy: self.send_policy,
+                        spend_limits: self.spend_limits,
+                        fee_ledger: self.fee_ledger.clone(),
                         rpc_client: self.rpc_client.clone(),
                         fee_payer_filepath: self.fee_payer_filepath.clone(),
                         fee_private_key: self.fee_private_key.clone(),
                         solo_collecting_data: self.solo_collecting_data.clone(),
                         pool_collecting_data: self.pool_collecting_data.clone(),
                         sub_private_filepath: None,
+                        rpc_endpoints: self.rpc_endpoints.clone(),
+                        ws_url: self.ws_url.clone(),
+                        output: self.output,
                     };
 
                     // 创建ClaimArgs的副本，但不包含sub_private以避免递归批量领取
@@ -256,7 +266,7 @@ impl Miner {
             .ok();
     }
 
-    async fn claim_from_pool(
+    pub async fn claim_from_pool(
         &self,
         args: ClaimArgs,
         pool: &Pool,
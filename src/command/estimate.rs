@@ -0,0 +1,228 @@
+use std::sync::Arc;
+
+use colored::*;
+use eore_api::state::Config;
+use eore_boost_api::state::stake_pda;
+use solana_program::native_token::lamports_to_sol;
+use solana_rpc_client::spinner;
+use solana_sdk::signature::Signer;
+use tabled::{
+    settings::{
+        object::{Columns, Rows},
+        Alignment, Remove, Style,
+    },
+    Table,
+};
+
+use super::{
+    benchmark::hash_for,
+    mine::{expected_reward, MINE_COMPUTE_UNITS},
+};
+use crate::{
+    args::EstimateArgs,
+    utils::{get_boost_config, get_boosts, get_config, get_stake, TableData, TableSectionTitle},
+    Miner,
+};
+
+const BENCHMARK_DURATION: i64 = 10;
+const ROUND_DURATION: u64 = 60;
+const ROUNDS_PER_HOUR: f64 = 3600.0 / ROUND_DURATION as f64;
+
+impl Miner {
+    pub async fn estimate(&self, args: EstimateArgs) {
+        // Measure hashpower, unless given
+        let hashrate = match args.hashrate {
+            Some(hashrate) => hashrate,
+            None => {
+                let cores = self.benchmark_parse_cores(&args.cores);
+                self.benchmark_check_cores(cores);
+                let progress_bar = Arc::new(spinner::new_progress_bar());
+                progress_bar.set_message(format!(
+                    "Benchmarking {} cores. This will take {} sec...",
+                    cores, BENCHMARK_DURATION
+                ));
+                let hashrate =
+                    hash_for(cores, BENCHMARK_DURATION).saturating_div(BENCHMARK_DURATION as u64);
+                progress_bar.finish_and_clear();
+                hashrate
+            }
+        };
+        let config = match get_config(&self.rpc_client).await {
+            Ok(config) => config,
+            Err(err) => {
+                println!("{} {}", "ERROR".bold().red(), err);
+                return;
+            }
+        };
+
+        // Expected round outcome
+        let hash_time = ROUND_DURATION.saturating_sub(args.buffer_time);
+        let hashes = hashrate.saturating_mul(hash_time) as f64;
+        let outcome = RoundOutcome::new(&config, hashes);
+        let accounts = args.accounts.max(1) as f64;
+
+        let mut data = vec![];
+        data.push(TableData {
+            key: "Hashpower".to_string(),
+            value: format!("{} H/sec", hashrate),
+        });
+        data.push(TableData {
+            key: "Hashes per round".to_string(),
+            value: format!("{} ({} sec)", hashes, hash_time),
+        });
+        data.push(TableData {
+            key: "Accounts".to_string(),
+            value: args.accounts.max(1).to_string(),
+        });
+        data.push(TableData {
+            key: "Min difficulty".to_string(),
+            value: config.min_difficulty.to_string(),
+        });
+        data.push(TableData {
+            key: "Expected best difficulty".to_string(),
+            value: format!("{:.2}", outcome.difficulty),
+        });
+        data.push(TableData {
+            key: "Chance to reach min difficulty".to_string(),
+            value: format!("{:.2}%", outcome.min_difficulty_chance * 100.0),
+        });
+
+        // Rewards
+        let len1 = data.len();
+        let reward = outcome.reward * accounts;
+        for (period, rounds) in periods() {
+            data.push(TableData {
+                key: period.to_string(),
+                value: format!("{:.11} BITZ", reward * rounds),
+            });
+        }
+
+        // Fees
+        let len2 = data.len();
+        let cu_price = if self.dynamic_fee {
+            self.get_dynamic_priority_fee()
+                .await
+                .unwrap_or(self.priority_fee.unwrap_or(0))
+        } else {
+            self.priority_fee.unwrap_or(0)
+        };
+        let fee = lamports_to_sol(self.fee_cost(MINE_COMPUTE_UNITS, cu_price)) * accounts;
+        data.push(TableData {
+            key: "Priority fee".to_string(),
+            value: format!("{} microlamports", cu_price),
+        });
+        for (period, rounds) in periods() {
+            data.push(TableData {
+                key: period.to_string(),
+                value: format!("{:.9} ETH", fee * rounds),
+            });
+        }
+        if let Some(bitz_price) = self.spend_limits.bitz_price {
+            let net = format!(
+                "{:.9} ETH",
+                (reward * bitz_price - fee) * ROUNDS_PER_HOUR * 24.0
+            );
+            data.push(TableData {
+                key: "Net per day".to_string(),
+                value: if reward * bitz_price >= fee {
+                    net.bold().green().to_string()
+                } else {
+                    net.bold().red().to_string()
+                },
+            });
+        }
+
+        // Boosts
+        let len3 = data.len();
+        self.fetch_boost_shares(&mut data).await;
+
+        // Build table
+        let has_boosts = data.len() > len3;
+        let mut table = Table::new(data);
+        table.with(Remove::row(Rows::first()));
+        table.modify(Columns::single(1), Alignment::right());
+        table.with(Style::blank());
+        table.section_title(0, "Round");
+        table.section_title(len1, "Expected rewards");
+        table.section_title(len2, "Fees");
+        if has_boosts {
+            table.section_title(len3, "Boosts");
+        }
+        println!("{table}\n");
+    }
+
+    /// Share of boost rewards each boost receives, and the signer's share of it.
+    async fn fetch_boost_shares(&self, data: &mut Vec<TableData>) {
+        let Ok(boosts) = get_boosts(&self.rpc_client).await else {
+            return;
+        };
+        let boost_config = get_boost_config(&self.rpc_client).await;
+        let authority = self.signer().pubkey();
+        for (address, boost) in boosts {
+            let weight_share = if boost_config.total_weight > 0 {
+                boost.weight as f64 / boost_config.total_weight as f64
+            } else {
+                0.0
+            };
+            let stake_share =
+                match get_stake(&self.rpc_client, stake_pda(authority, address).0).await {
+                    Ok(stake) if boost.total_deposits > 0 => {
+                        stake.balance as f64 / boost.total_deposits as f64
+                    }
+                    _ => 0.0,
+                };
+            data.push(TableData {
+                key: boost.mint.to_string(),
+                value: format!(
+                    "{:.2}% of boost rewards, your share {:.4}%",
+                    weight_share * 100.0,
+                    weight_share * stake_share * 100.0
+                ),
+            });
+        }
+    }
+}
+
+/// Expected outcome of one round with the given number of hashes.
+struct RoundOutcome {
+    difficulty: f64,
+    reward: f64,
+    min_difficulty_chance: f64,
+}
+
+impl RoundOutcome {
+    /// Each hash has difficulty of at least `d` with probability 2^-d, so the best
+    /// of `n` hashes reaches `d` with probability 1 - (1 - 2^-d)^n. Rounds that fall
+    /// short of the min difficulty keep collecting until they reach it.
+    fn new(config: &Config, hashes: f64) -> Self {
+        let min_difficulty = config.min_difficulty as u32;
+        let at_least = |d: u32| -> f64 {
+            let p = 2f64.powi(-(d as i32));
+            -(hashes * (-p).ln_1p()).exp_m1()
+        };
+        let mut difficulty = 0.0;
+        let mut reward = 0.0;
+        for d in min_difficulty..64 {
+            let p = if d == min_difficulty {
+                1.0 - at_least(d + 1)
+            } else {
+                at_least(d) - at_least(d + 1)
+            };
+            difficulty += p * d as f64;
+            reward += p * expected_reward(config, d);
+        }
+        Self {
+            difficulty,
+            reward,
+            min_difficulty_chance: at_least(min_difficulty),
+        }
+    }
+}
+
+fn periods() -> [(&'static str, f64); 3] {
+    [
+        ("Per round", 1.0),
+        ("Per hour", ROUNDS_PER_HOUR),
+        ("Per day", ROUNDS_PER_HOUR * 24.0),
+    ]
+}
@@ -0,0 +1,149 @@
+use colored::*;
+use solana_program::native_token::lamports_to_sol;
+use tabled::{
+    settings::{object::Columns, Alignment, Style},
+    Table, Tabled,
+};
+
+use crate::{
+    args::{FeesArgs, FeesCommand, FeesHistoryArgs},
+    send::{read_fee_history, read_landed_history},
+    utils::format_timestamp,
+    Miner,
+};
+
+#[derive(Tabled)]
+struct FeeHistoryData {
+    #[tabled(rename = "Time")]
+    time: String,
+    #[tabled(rename = "Strategy")]
+    strategy: String,
+    #[tabled(rename = "Percentile")]
+    percentile: String,
+    #[tabled(rename = "Sample")]
+    sample: String,
+    #[tabled(rename = "Smoothed")]
+    smoothed: String,
+    #[tabled(rename = "Fee")]
+    fee: String,
+}
+
+#[derive(Tabled)]
+struct LandedData {
+    #[tabled(rename = "Time")]
+    time: String,
+    #[tabled(rename = "Signature")]
+    signature: String,
+    #[tabled(rename = "CU Limit")]
+    cu_limit: String,
+    #[tabled(rename = "CU Price")]
+    cu_price: String,
+    #[tabled(rename = "Fee")]
+    fee: String,
+    #[tabled(rename = "Attempts")]
+    attempts: String,
+}
+
+impl Miner {
+    pub fn fees(&self, args: FeesArgs) {
+        match args.command {
+            FeesCommand::History(args) => self.fees_history(args),
+            FeesCommand::Landed(args) => self.fees_landed(args),
+        }
+    }
+
+    fn fees_history(&self, args: FeesHistoryArgs) {
+        let records = match read_fee_history() {
+            Ok(records) => records,
+            Err(err) => {
+                println!(
+                    "{} Failed to read fee history: {}",
+                    "ERROR".bold().red(),
+                    err
+                );
+                return;
+            }
+        };
+        if records.is_empty() {
+            println!("No fee estimates recorded yet. Run with --dynamic-fee to record them.");
+            return;
+        }
+
+        // Show the most recent estimates, newest first
+        let recent: Vec<_> = records.iter().rev().take(args.limit).collect();
+        let data: Vec<_> = recent
+            .iter()
+            .map(|record| FeeHistoryData {
+                time: format_timestamp(record.timestamp),
+                strategy: format!("{:?}", record.strategy),
+                percentile: format!("p{}", record.percentile),
+                sample: record.sample.to_string(),
+                smoothed: record.smoothed.to_string(),
+                fee: record.fee.to_string(),
+            })
+            .collect();
+        let mut table = Table::new(data);
+        table.with(Style::blank());
+        table.modify(Columns::new(2..), Alignment::right());
+        println!("\n{table}\n");
+
+        // Summarize the fees that were used
+        let fees: Vec<u64> = recent.iter().map(|record| record.fee).collect();
+        let min = fees.iter().min().copied().unwrap_or(0);
+        let max = fees.iter().max().copied().unwrap_or(0);
+        let avg = fees.iter().sum::<u64>() / fees.len() as u64;
+        println!(
+            "{} estimates (microlamports per CU): min {} avg {} max {}\n",
+            fees.len(),
+            min,
+            avg,
+            max
+        );
+    }
+
+    fn fees_landed(&self, args: FeesHistoryArgs) {
+        let records = match read_landed_history() {
+            Ok(records) => records,
+            Err(err) => {
+                println!(
+                    "{} Failed to read landed transactions: {}",
+                    "ERROR".bold().red(),
+                    err
+                );
+                return;
+            }
+        };
+        if records.is_empty() {
+            println!("No landed transactions recorded yet.");
+            return;
+        }
+
+        // Show the most recent transactions, newest first
+        let recent: Vec<_> = records.iter().rev().take(args.limit).collect();
+        let data: Vec<_> = recent
+            .iter()
+            .map(|record| LandedData {
+                time: format_timestamp(record.timestamp),
+                signature: record.signature.clone(),
+                cu_limit: record.cu_limit.to_string(),
+                cu_price: record.cu_price.to_string(),
+                fee: format!("{} ETH", lamports_to_sol(record.fee)),
+                attempts: record.attempts.to_string(),
+            })
+            .collect();
+        let mut table = Table::new(data);
+        table.with(Style::blank());
+        table.modify(Columns::new(2..), Alignment::right());
+        println!("\n{table}\n");
+
+        // Summarize the fees that were paid
+        let total: u64 = recent.iter().map(|record| record.fee).sum();
+        let attempts: usize = recent.iter().map(|record| record.attempts).sum();
+        println!(
+            "{} transactions: {} ETH in fees, {:.1} attempts on average\n",
+            recent.len(),
+            lamports_to_sol(total),
+            attempts as f64 / recent.len() as f64
+        );
+    }
+}
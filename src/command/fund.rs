@@ -0,0 +1,180 @@
+use colored::*;
+use solana_program::{
+    native_token::{lamports_to_sol, sol_to_lamports},
+    pubkey::Pubkey,
+    system_instruction,
+};
+use solana_sdk::signature::{Keypair, Signer};
+use tabled::{
+    settings::{Alignment, Style},
+    Table, Tabled,
+};
+
+use crate::{
+    args::FundArgs,
+    error::Error,
+    utils::{ask_confirm, ComputeBudget},
+    Miner,
+};
+
+// 单笔交易打包的最大转账数量（受限于交易大小 1232 字节）
+const TRANSFERS_PER_TX: usize = 20;
+
+// 每笔系统转账预留的计算单元
+const CU_PER_TRANSFER: u32 = 1_000;
+
+#[derive(Tabled)]
+struct FundData {
+    #[tabled(rename = "账户地址")]
+    address: String,
+    #[tabled(rename = "当前ETH余额")]
+    balance: String,
+    #[tabled(rename = "补充数量")]
+    top_up: String,
+}
+
+impl Miner {
+    pub async fn fund(&self, args: FundArgs) -> Result<(), Error> {
+        let filepath = match &self.sub_private_filepath {
+            Some(path) => path.clone(),
+            None => {
+                println!("{}", "错误: 未指定子钱包私钥文件。请使用 --sub-private 参数指定JSON私钥文件。".red());
+                return Err(Error::Internal("未指定子钱包私钥文件".to_string()));
+            }
+        };
+
+        // 校验阈值
+        if args.top_up_to < args.min {
+            println!("{}", "错误: --top-up-to 不能小于 --min".red());
+            return Err(Error::Internal("--top-up-to 不能小于 --min".to_string()));
+        }
+        let min_lamports = sol_to_lamports(args.min);
+        let target_lamports = sol_to_lamports(args.top_up_to);
+
+        // 读取JSON文件，获取私钥列表
+        let file_content = match std::fs::read_to_string(&filepath) {
+            Ok(content) => content,
+            Err(e) => {
+                println!("{}", format!("错误: 无法读取私钥文件 '{}': {}", filepath, e).red());
+                return Err(Error::Internal("无法读取私钥文件".to_string()));
+            }
+        };
+        let private_keys: Vec<String> = match serde_json::from_str(&file_content) {
+            Ok(keys) => keys,
+            Err(e) => {
+                println!("{}", format!("错误: JSON文件格式不正确: {}", e).red());
+                return Err(Error::Internal("JSON文件格式不正确".to_string()));
+            }
+        };
+        if private_keys.is_empty() {
+            println!("{}", "警告: 私钥文件中没有找到任何私钥。".yellow());
+            return Ok(());
+        }
+
+        // 查询每个子钱包余额，找出低于阈值的账户
+        println!("正在查询 {} 个子钱包的ETH余额...", private_keys.len());
+        let fee_payer = self.fee_payer();
+        let mut transfers: Vec<(Pubkey, u64)> = vec![];
+        let mut fund_data = vec![];
+        for private_key in private_keys {
+            let keypair = match bs58::decode(&private_key)
+                .into_vec()
+                .ok()
+                .and_then(|bytes| Keypair::from_bytes(&bytes).ok())
+            {
+                Some(keypair) => keypair,
+                None => {
+                    println!("{}", format!("错误: 私钥格式错误: {}", private_key).red());
+                    continue;
+                }
+            };
+            let pubkey = keypair.pubkey();
+            if pubkey == fee_payer.pubkey() {
+                continue;
+            }
+            let balance = match self.rpc_client.get_balance(&pubkey).await {
+                Ok(balance) => balance,
+                Err(e) => {
+                    println!("{}", format!("警告: 账户 {} 查询失败: {:?}", pubkey, e).yellow());
+                    continue;
+                }
+            };
+            if balance >= min_lamports {
+                continue;
+            }
+            let top_up = target_lamports.saturating_sub(balance);
+            transfers.push((pubkey, top_up));
+            fund_data.push(FundData {
+                address: pubkey.to_string(),
+                balance: format!("{} ETH", lamports_to_sol(balance)),
+                top_up: format!("{} ETH", lamports_to_sol(top_up)),
+            });
+        }
+
+        if transfers.is_empty() {
+            println!("{}", format!("所有子钱包余额均不低于 {} ETH，无需补充。", args.min).green());
+            return Ok(());
+        }
+
+        // 预览
+        let total: u64 = transfers.iter().map(|(_, amount)| amount).sum();
+        let num_txs = transfers.len().div_ceil(TRANSFERS_PER_TX);
+        let mut table = Table::new(fund_data);
+        table.with(Style::modern()).with(Alignment::center());
+        println!("{}", table);
+        println!(
+            "付款地址: {}\n共 {} 个子钱包需要补充，合计 {} ETH，将打包为 {} 笔交易。",
+            fee_payer.pubkey(),
+            transfers.len(),
+            lamports_to_sol(total),
+            num_txs
+        );
+        if args.dry_run {
+            println!("{}", "预览模式 (--dry-run)，未发送任何交易。".yellow());
+            return Ok(());
+        }
+
+        // Confirm user wants to fund
+        if !ask_confirm(
+            format!(
+                "\nYou are about to send {} to {} wallets.\n\nAre you sure you want to continue? [Y/n]",
+                format!("{} ETH", lamports_to_sol(total)).bold(),
+                transfers.len(),
+            )
+            .as_str(),
+        ) {
+            return Ok(());
+        }
+
+        // 使用 fee payer 作为签名者发送转账
+        let funder = Miner {
+            keypair_filepath: self.fee_payer_filepath.clone(),
+            private_key: self.fee_private_key.clone(),
+            sub_private_filepath: None,
+            ..self.clone()
+        };
+        let mut failed = 0;
+        for (i, chunk) in transfers.chunks(TRANSFERS_PER_TX).enumerate() {
+            let ixs: Vec<_> = chunk
+                .iter()
+                .map(|(to, amount)| system_instruction::transfer(&fee_payer.pubkey(), to, *amount))
+                .collect();
+            println!("发送第 {}/{} 笔转账交易 ({} 个地址)...", i + 1, num_txs, chunk.len());
+            let cus = CU_PER_TRANSFER * chunk.len() as u32;
+            if let Err(err) = funder
+                .send_and_confirm(&ixs, ComputeBudget::Fixed(cus), false)
+                .await
+            {
+                failed += chunk.len();
+                println!("{}", format!("第 {} 笔交易失败: {}", i + 1, err).red());
+            }
+        }
+
+        if failed > 0 {
+            println!("{}", format!("{} 个子钱包补充失败，请重新运行该命令。", failed).yellow());
+        } else {
+            println!("{}", format!("成功为 {} 个子钱包补充ETH", transfers.len()).green());
+        }
+        Ok(())
+    }
+}
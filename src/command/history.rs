@@ -0,0 +1,280 @@
+use std::{collections::BTreeMap, str::FromStr, sync::Arc};
+
+use chrono::{Duration, Local, NaiveDate, TimeZone};
+use colored::*;
+use eore_api::state::proof_pda;
+use serde::Serialize;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_program::{native_token::lamports_to_sol, pubkey::Pubkey};
+use solana_rpc_client::spinner;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::{Signature, Signer},
+};
+use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
+use tabled::{
+    settings::{object::Columns, Alignment, Style},
+    Table, Tabled,
+};
+
+use crate::{
+    args::{ExportFormat, HistoryArgs},
+    error::Error,
+    utils::{amount_u64_to_f64, format_timestamp, mine_event_from_logs, OutputFormat},
+    Miner,
+};
+
+/// Maximum number of signatures returned per `getSignaturesForAddress` page.
+const SIGNATURES_PAGE_SIZE: usize = 1000;
+
+/// Default history window when `--since` is not given.
+const DEFAULT_DAYS: i64 = 7;
+
+/// Mining activity of one day, in the local timezone.
+#[derive(Clone, Debug, Default, Serialize)]
+struct DailyHistory {
+    date: String,
+    transactions: u64,
+    base_reward: f64,
+    boost_reward: f64,
+    total_reward: f64,
+    average_difficulty: f64,
+    boost_share: f64,
+    fees: f64,
+    #[serde(skip)]
+    difficulty_sum: u64,
+}
+
+#[derive(Tabled)]
+struct DailyHistoryData {
+    #[tabled(rename = "Date")]
+    date: String,
+    #[tabled(rename = "Txs")]
+    transactions: String,
+    #[tabled(rename = "Base Reward")]
+    base_reward: String,
+    #[tabled(rename = "Boost Reward")]
+    boost_reward: String,
+    #[tabled(rename = "Total Reward")]
+    total_reward: String,
+    #[tabled(rename = "Avg Difficulty")]
+    average_difficulty: String,
+    #[tabled(rename = "Boost Share")]
+    boost_share: String,
+    #[tabled(rename = "Fees")]
+    fees: String,
+}
+
+impl Miner {
+    pub async fn history(&self, args: HistoryArgs) -> Result<(), Error> {
+        let authority = match &args.authority {
+            Some(authority) => Pubkey::from_str(authority)?,
+            None => self.signer().pubkey(),
+        };
+        let since = match &args.since {
+            Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| {
+                    Error::Internal(format!("Invalid date {}, expected YYYY-MM-DD", date))
+                })?
+                .and_hms_opt(0, 0, 0)
+                .and_then(|dt| Local.from_local_datetime(&dt).earliest())
+                .map(|dt| dt.timestamp())
+                .ok_or_else(|| Error::Internal(format!("Invalid date {}", date)))?,
+            None => (Local::now() - Duration::days(DEFAULT_DAYS)).timestamp(),
+        };
+
+        // Collect successful transactions on the proof since the given date
+        let proof_address = proof_pda(authority).0;
+        let progress_bar = Arc::new(spinner::new_progress_bar());
+        progress_bar.set_message("Fetching signatures...");
+        let signatures = self.fetch_signatures_since(&proof_address, since).await?;
+
+        // Aggregate mine events per day
+        let mut days: BTreeMap<String, DailyHistory> = BTreeMap::new();
+        for (i, signature) in signatures.iter().enumerate() {
+            progress_bar.set_message(format!(
+                "Fetching transactions... ({}/{})",
+                i + 1,
+                signatures.len()
+            ));
+            let tx = match self
+                .rpc_client
+                .get_transaction(signature, UiTransactionEncoding::Json)
+                .await
+            {
+                Ok(tx) => tx,
+                Err(err) => {
+                    progress_bar.println(format!(
+                        "{} Failed to fetch transaction {}: {}",
+                        "WARNING".bold().yellow(),
+                        signature,
+                        err
+                    ));
+                    continue;
+                }
+            };
+            let Some(meta) = tx.transaction.meta else {
+                continue;
+            };
+            let OptionSerializer::Some(log_messages) = &meta.log_messages else {
+                continue;
+            };
+            let Some(event) = mine_event_from_logs(log_messages) else {
+                continue;
+            };
+            let date = Local
+                .timestamp_opt(tx.block_time.unwrap_or_default(), 0)
+                .unwrap()
+                .format("%Y-%m-%d")
+                .to_string();
+            let day = days.entry(date.clone()).or_insert_with(|| DailyHistory {
+                date,
+                ..Default::default()
+            });
+            day.transactions += 1;
+            day.base_reward += amount_u64_to_f64(event.net_base_reward);
+            day.boost_reward += amount_u64_to_f64(event.net_miner_boost_reward);
+            day.total_reward += amount_u64_to_f64(event.net_reward);
+            day.difficulty_sum += event.difficulty;
+            day.fees += lamports_to_sol(meta.fee);
+        }
+        progress_bar.finish_and_clear();
+        for day in days.values_mut() {
+            day.average_difficulty = day.difficulty_sum as f64 / day.transactions as f64;
+            if day.total_reward > 0.0 {
+                day.boost_share = day.boost_reward / day.total_reward;
+            }
+        }
+        let history: Vec<DailyHistory> = days.into_values().collect();
+
+        // Export, as JSON in `--output json` mode
+        let export = args
+            .export
+            .or((self.output == OutputFormat::Json).then_some(ExportFormat::Json));
+        if let Some(format) = export {
+            let output = match format {
+                ExportFormat::Csv => history_to_csv(&history),
+                ExportFormat::Json => serde_json::to_string_pretty(&history)
+                    .map_err(|err| Error::Internal(err.to_string()))?,
+            };
+            match &args.file {
+                Some(path) => {
+                    std::fs::write(path, output).map_err(|err| {
+                        Error::Internal(format!("Failed to write {}: {}", path, err))
+                    })?;
+                    println!("Exported {} days to {}", history.len(), path);
+                }
+                None => println!("{}", output),
+            }
+            return Ok(());
+        }
+
+        // Print table
+        if history.is_empty() {
+            println!(
+                "No mining transactions found for {} since {}",
+                authority,
+                format_timestamp(since)
+            );
+            return Ok(());
+        }
+        let data: Vec<_> = history
+            .iter()
+            .map(|day| DailyHistoryData {
+                date: day.date.clone(),
+                transactions: day.transactions.to_string(),
+                base_reward: format!("{:.11}", day.base_reward),
+                boost_reward: format!("{:.11}", day.boost_reward),
+                total_reward: format!("{:.11}", day.total_reward),
+                average_difficulty: format!("{:.2}", day.average_difficulty),
+                boost_share: format!("{:.2}%", day.boost_share * 100.0),
+                fees: format!("{:.9} ETH", day.fees),
+            })
+            .collect();
+        let mut table = Table::new(data);
+        table.with(Style::blank());
+        table.modify(Columns::new(1..), Alignment::right());
+        println!("\n{table}\n");
+
+        // Summarize
+        let transactions: u64 = history.iter().map(|day| day.transactions).sum();
+        let total_reward: f64 = history.iter().map(|day| day.total_reward).sum();
+        let fees: f64 = history.iter().map(|day| day.fees).sum();
+        println!(
+            "{} transactions over {} days: {:.11} BITZ earned, {:.9} ETH in fees\n",
+            transactions,
+            history.len(),
+            total_reward,
+            fees
+        );
+        Ok(())
+    }
+
+    /// Pages back through the signatures of an address until the given unix
+    /// timestamp. Failed transactions are skipped.
+    async fn fetch_signatures_since(
+        &self,
+        address: &Pubkey,
+        since: i64,
+    ) -> Result<Vec<Signature>, Error> {
+        let mut signatures = vec![];
+        let mut before = None;
+        loop {
+            let config = GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some(SIGNATURES_PAGE_SIZE),
+                commitment: Some(CommitmentConfig::confirmed()),
+            };
+            let page = self
+                .rpc_client
+                .get_signatures_for_address_with_config(address, config)
+                .await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            before = Some(
+                Signature::from_str(&last.signature)
+                    .map_err(|err| Error::Internal(err.to_string()))?,
+            );
+            let is_last_page = page.len() < SIGNATURES_PAGE_SIZE;
+            let mut reached_since = false;
+            for status in page {
+                if status.block_time.unwrap_or_default() < since {
+                    reached_since = true;
+                    break;
+                }
+                if status.err.is_some() {
+                    continue;
+                }
+                if let Ok(signature) = Signature::from_str(&status.signature) {
+                    signatures.push(signature);
+                }
+            }
+            if reached_since || is_last_page {
+                break;
+            }
+        }
+        Ok(signatures)
+    }
+}
+
+fn history_to_csv(history: &[DailyHistory]) -> String {
+    let mut csv = String::from(
+        "date,transactions,base_reward,boost_reward,total_reward,average_difficulty,boost_share,fees\n",
+    );
+    for day in history {
+        csv.push_str(&format!(
+            "{},{},{:.11},{:.11},{:.11},{:.2},{:.4},{:.9}\n",
+            day.date,
+            day.transactions,
+            day.base_reward,
+            day.boost_reward,
+            day.total_reward,
+            day.average_difficulty,
+            day.boost_share,
+            day.fees
+        ));
+    }
+    csv
+}
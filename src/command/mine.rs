@@ -1,7 +1,6 @@
 use std::{
     io::stdout,
     sync::{Arc, RwLock},
-    thread::sleep,
     time::{Duration, Instant},
     usize,
 };
@@ -18,12 +17,13 @@ use drillx::{
     Hash, Solution,
 };
 use eore_api::{
-    consts::{BUS_ADDRESSES, BUS_COUNT, EPOCH_DURATION},
+    consts::{BUS_ADDRESSES, BUS_COUNT, CONFIG_ADDRESS, EPOCH_DURATION, ONE_MINUTE, TOLERANCE},
     event::MineEvent,
     state::{proof_pda, Bus, Config},
 };
+use ore_pool_types::PoolMemberMiningEvent;
 use rand::Rng;
-use solana_program::pubkey::Pubkey;
+use solana_program::{native_token::lamports_to_sol, pubkey::Pubkey};
 use solana_rpc_client::spinner;
 use solana_sdk::{signature::Signature, signer::Signer};
 use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
@@ -39,142 +39,353 @@ use tabled::{
 
 use crate::{
     args::CollectArgs,
-    error::Error,
+    error::{Error, ErrorClass},
     utils::{
         amount_u64_to_f64, format_duration, format_timestamp, get_clock, get_config,
-        get_updated_proof_with_authority, ComputeBudget, PoolCollectingData, SoloCollectingData,
+        get_updated_proof_with_authority, AccountSubscription, ComputeBudget, EventSubscription,
+        PoolCollectingData, SoloCollectingData,
     },
     Miner,
 };
 
-use super::pool::Pool;
+use super::{
+    auto_balance::AutoBalance,
+    pool::{record_pool_event, Pool, PoolFailover},
+    submission::SubmissionQueue,
+};
+
+// Compute budget the mine transaction used before budgets were simulated
+pub const MINE_COMPUTE_UNITS: u32 = 750_000;
+
+// Seconds to keep collecting when the best solution is not worth its fee
+const UNPROFITABLE_DELAY: u64 = 15;
+
+// Most extra seconds spent on an unprofitable round before submitting anyway.
+// The challenge only changes once a solution lands, so the round cannot be skipped.
+const MAX_UNPROFITABLE_TIME: u64 = 120;
 
 impl Miner {
     pub async fn mine(&self, args: CollectArgs) -> Result<(), Error> {
-        match args.pool_url {
-            Some(ref pool_url) => {
-                let pool = &Pool {
-                    http_client: reqwest::Client::new(),
-                    pool_url: pool_url.clone(),
-                };
-                self.collect_pool(args, pool).await?;
-            }
-            None => {
-                self.collect_solo(args).await;
-            }
+        if args.pool_url.is_empty() {
+            self.collect_solo(args).await?;
+        } else {
+            self.collect_pool(args).await?;
         }
         Ok(())
     }
 
-    async fn collect_solo(&self, args: CollectArgs) {
+    async fn collect_solo(&self, args: CollectArgs) -> Result<(), Error> {
         // Open account, if needed.
         self.open().await;
 
         // Check num threads
-        let cores_str = args.cores;
-        let cores = if cores_str == "ALL" {
-            num_cpus::get() as u64
-        } else {
-            cores_str.parse::<u64>().unwrap()
-        };
+        let cores = self.parse_cores(args.cores.clone());
         self.check_num_cores(cores);
 
         // Get verbose flag
         let verbose = args.verbose;
 
-        // Generate addresses
+        // Say which spending checks are off, as none of them is on by default
+        if self.spend_limits.bitz_price.is_none() {
+            println!(
+                "{} Profitability check is off, every solution is submitted whatever its fee. Set --bitz-price to hold back solutions worth less than their fee.",
+                "NOTE".bold().yellow()
+            );
+        }
+        if self.spend_limits.min_balance <= 0.0 {
+            println!(
+                "{} Minimum balance check is off, sending continues until the fee payer runs dry. Set --min-balance to pause before.",
+                "NOTE".bold().yellow()
+            );
+        }
+
+        // Subscribe to proof and config updates
         let signer = self.signer();
-        let _proof_address = proof_pda(signer.pubkey()).0;
-        let boost_config_address = eore_boost_api::state::config_pda().0;
+        let mut proof_sub = self
+            .ws_url
+            .as_ref()
+            .map(|ws_url| AccountSubscription::new(ws_url.clone(), proof_pda(signer.pubkey()).0));
+        let config_sub = self
+            .ws_url
+            .as_ref()
+            .map(|ws_url| AccountSubscription::new(ws_url.clone(), CONFIG_ADDRESS));
 
         // Start collecting loop
         let mut last_hash_at = 0;
+        let mut failures = 0;
+        let mut last_class = None;
         loop {
-            // Fetch accounts
-            let config = get_config(&self.rpc_client).await;
-            let proof =
-                get_updated_proof_with_authority(&self.rpc_client, signer.pubkey(), last_hash_at)
-                    .await
-                    .expect("Failed to fetch proof account");
+            match self
+                .collect_solo_round(
+                    &args,
+                    cores,
+                    last_hash_at,
+                    proof_sub.as_mut(),
+                    config_sub.as_ref(),
+                )
+                .await
+            {
+                Ok((sig, hash_at)) => {
+                    // A simulated round never lands, so there is no event to wait for
+                    if self.simulate {
+                        return Ok(());
+                    }
 
-            // Log collecting table
-            self.update_solo_collecting_table(verbose);
+                    // Track timestamp
+                    last_hash_at = hash_at;
+                    failures = 0;
+                    last_class = None;
+                    self.fetch_solo_mine_event(sig, verbose).await;
+                }
+                Err(err) => {
+                    let class = err.class();
+                    self.push_solo_failed_row(verbose);
+                    println!("{}: {} ({:?})", "ERROR".bold().red(), err, class);
+                    if class == ErrorClass::Fatal {
+                        return Err(err);
+                    }
 
-            // Track timestamp
-            last_hash_at = proof.last_hash_at;
+                    // Back off according to the policy of the error class
+                    if last_class != Some(class) {
+                        failures = 0;
+                    }
+                    last_class = Some(class);
+                    let policy = class.policy();
+                    let delay = if failures >= policy.max_retries {
+                        println!(
+                            "{} {:?} errors persist after {} retries. Cooling down for {}s...",
+                            "WARNING".bold().yellow(),
+                            class,
+                            failures,
+                            policy.max_backoff.as_secs()
+                        );
+                        failures = 0;
+                        policy.max_backoff
+                    } else {
+                        let delay = policy.backoff(failures);
+                        failures += 1;
+                        delay
+                    };
+                    if class == ErrorClass::InsufficientFunds {
+                        println!(
+                            "{} Insufficient funds for transaction. Top up {} and mining will resume.",
+                            "WARNING".bold().yellow(),
+                            self.fee_payer().pubkey()
+                        );
+                    }
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
 
-            // Calculate cutoff time
-            let cutoff_time = self.get_cutoff(proof.last_hash_at, args.buffer_time).await;
+    /// Runs a single solo round: waits for the next challenge, hashes until the
+    /// cutoff and submits the best solution. Blockhash expiry and epoch resets are
+    /// retried in place with the same solution; every other error is returned to
+    /// the collecting loop. On success returns the signature and the `last_hash_at`
+    /// of the proof that was mined against.
+    async fn collect_solo_round(
+        &self,
+        args: &CollectArgs,
+        cores: u64,
+        last_hash_at: i64,
+        proof_sub: Option<&mut AccountSubscription>,
+        config_sub: Option<&AccountSubscription>,
+    ) -> Result<(Signature, i64), Error> {
+        // Fetch accounts
+        let signer = self.signer();
+        let boost_config_address = eore_boost_api::state::config_pda().0;
+        let config = match config_sub.and_then(|sub| sub.latest()) {
+            Some(data) => *Config::try_from_bytes(&data)?,
+            None => get_config(&self.rpc_client).await?,
+        };
+        let proof = get_updated_proof_with_authority(
+            &self.rpc_client,
+            signer.pubkey(),
+            last_hash_at,
+            proof_sub,
+        )
+        .await?;
+
+        // Log collecting table
+        self.update_solo_collecting_table(args.verbose);
+
+        // Calculate cutoff time
+        let cutoff_time = self.get_cutoff(proof.last_hash_at, args.buffer_time).await?;
+
+        // Build nonce indices
+        let mut nonce_indices = Vec::with_capacity(cores as usize);
+        for n in 0..(cores) {
+            let nonce = u64::MAX.saturating_div(cores).saturating_mul(n);
+            nonce_indices.push(nonce);
+        }
 
-            // Build nonce indices
-            let mut nonce_indices = Vec::with_capacity(cores as usize);
-            for n in 0..(cores) {
-                let nonce = u64::MAX.saturating_div(cores).saturating_mul(n);
-                nonce_indices.push(nonce);
+        // Run drillx
+        let mut solution = Self::find_hash_par(
+            proof.challenge,
+            cutoff_time,
+            cores,
+            config.min_difficulty as u32,
+            nonce_indices.as_slice(),
+            None,
+        )
+        .await;
+
+        // Keep hashing while the best solution is worth less than its fee
+        if let Some(bitz_price) = self.spend_limits.bitz_price {
+            // Price the fee at the compute units the mine transaction simulates to
+            let ixs = [
+                eore_api::sdk::auth(proof_pda(signer.pubkey()).0),
+                eore_api::sdk::mine(
+                    signer.pubkey(),
+                    signer.pubkey(),
+                    self.find_bus().await,
+                    solution,
+                    boost_config_address,
+                ),
+            ];
+            let cu_limit = self
+                .simulate_compute_units(&ixs, &self.fee_payer().pubkey())
+                .await
+                .unwrap_or(MINE_COMPUTE_UNITS);
+
+            // Every extra second may land the solution later, past the liveness penalty
+            let hashed_at = match get_clock(&self.rpc_client).await {
+                Ok(clock) => clock.unix_timestamp,
+                Err(_) => chrono::Utc::now().timestamp(),
+            };
+            let mut unprofitable_time = 0;
+            loop {
+                let difficulty = solution.to_hash().difficulty();
+                let landed_at = hashed_at + unprofitable_time as i64;
+                let reward = expected_reward(&config, difficulty)
+                    * liveness_factor(proof.last_hash_at, landed_at);
+                let cu_price = self.expected_cu_price();
+                let cost = lamports_to_sol(self.fee_cost(cu_limit, cu_price));
+                if reward * bitz_price >= cost {
+                    break;
+                }
+                if unprofitable_time >= MAX_UNPROFITABLE_TIME {
+                    println!(
+                        "{} Score {} is still worth less than its fee after {}s more. Submitting anyway...",
+                        "WARNING".bold().yellow(),
+                        difficulty,
+                        unprofitable_time
+                    );
+                    break;
+                }
+                unprofitable_time += UNPROFITABLE_DELAY;
+                println!(
+                    "{} Score {} earns ~{:.11} BITZ ({:.9} ETH), less than the ~{:.9} ETH fee. Collecting for another {}s...",
+                    "WARNING".bold().yellow(),
+                    difficulty,
+                    reward,
+                    reward * bitz_price,
+                    cost,
+                    UNPROFITABLE_DELAY
+                );
+
+                // Continue from fresh nonces, keeping the best solution found so far
+                let nonce_indices: Vec<u64> = nonce_indices
+                    .iter()
+                    .map(|nonce| {
+                        nonce.saturating_add(
+                            rand::thread_rng().gen_range(0..u64::MAX.saturating_div(cores * 2)),
+                        )
+                    })
+                    .collect();
+                let candidate = Self::find_hash_par(
+                    proof.challenge,
+                    UNPROFITABLE_DELAY,
+                    cores,
+                    config.min_difficulty as u32,
+                    nonce_indices.as_slice(),
+                    None,
+                )
+                .await;
+                if candidate.to_hash().difficulty() > difficulty {
+                    solution = candidate;
+                }
             }
+        }
 
-            // Run drillx
-            let solution = Self::find_hash_par(
-                proof.challenge,
-                cutoff_time,
-                cores,
-                config.min_difficulty as u32,
-                nonce_indices.as_slice(),
-                None,
-            )
-            .await;
+        // The solution must land before the buffer runs out
+        let landing_deadline = Instant::now() + Duration::from_secs(args.buffer_time);
 
+        // Check for reset
+        let mut needs_reset = self.should_reset(config).await?;
+        let mut attempt = 0;
+        loop {
             // Build instruction set
             let mut ixs = vec![eore_api::sdk::auth(proof_pda(signer.pubkey()).0)];
-            let mut compute_budget = 750_000;
-
-            // Check for reset
-            if self.should_reset(config).await
-            // && rand::thread_rng().gen_range(0..100).eq(&0)
-            {
-                compute_budget += 100_000;
+            if needs_reset {
                 ixs.push(eore_api::sdk::reset(signer.pubkey()));
             }
 
             // Build mine ix
-            let mine_ix = eore_api::sdk::mine(
+            ixs.push(eore_api::sdk::mine(
                 signer.pubkey(),
                 signer.pubkey(),
                 self.find_bus().await,
                 solution,
                 boost_config_address,
-            );
-            ixs.push(mine_ix);
+            ));
 
             // Submit transaction
             match self
-                .send_and_confirm(&ixs, ComputeBudget::Fixed(compute_budget), false)
+                .send_and_confirm_with_deadline(
+                    &ixs,
+                    ComputeBudget::Dynamic,
+                    false,
+                    Some(landing_deadline),
+                )
                 .await
             {
-                Ok(sig) => self.fetch_solo_mine_event(sig, verbose).await,
+                Ok(sig) => return Ok((sig, proof.last_hash_at)),
                 Err(err) => {
-                    let collecting_data = SoloCollectingData::failed();
-                    let mut data = self.solo_collecting_data.write().unwrap();
-                    if !data.is_empty() {
-                        data.remove(0);
+                    let class = err.class();
+                    let policy = class.policy();
+                    match class {
+                        ErrorClass::NeedsReset | ErrorClass::BlockhashExpired
+                            if attempt < policy.max_retries =>
+                        {
+                            needs_reset |= class == ErrorClass::NeedsReset;
+                            println!(
+                                "{} {:?}. Resubmitting solution...",
+                                "WARNING".bold().yellow(),
+                                class
+                            );
+                            tokio::time::sleep(policy.backoff(attempt)).await;
+                            attempt += 1;
+                        }
+                        _ => return Err(err),
                     }
-                    data.insert(0, collecting_data);
-                    drop(data);
-
-                    // Log collecting table
-                    self.update_solo_collecting_table(verbose);
-                    println!("{}: {}", "ERROR".bold().red(), err);
-
-                    return;
                 }
             }
         }
     }
 
-    async fn collect_pool(&self, args: CollectArgs, pool: &Pool) -> Result<(), Error> {
-        // Register, if needed
-        let pool_member = pool.post_pool_register(self).await?;
-        let nonce_index = pool_member.id as u64;
+    fn push_solo_failed_row(&self, verbose: bool) {
+        let collecting_data = SoloCollectingData::failed();
+        let mut data = self.solo_collecting_data.write().unwrap();
+        if !data.is_empty() {
+            data.remove(0);
+        }
+        data.insert(0, collecting_data);
+        drop(data);
+
+        // Log collecting table
+        self.update_solo_collecting_table(verbose);
+    }
+
+    async fn collect_pool(&self, args: CollectArgs) -> Result<(), Error> {
+        // Register with every pool, if needed, expanding `auto` to the best probed pools
+        let pool_urls = self.resolve_pool_urls(&args.pool_url).await?;
+        let mut pools =
+            PoolFailover::new(self, &pool_urls, args.pool_failures, args.pool_mock).await?;
+
+        // Commit and claim the pool balance between rounds, if asked to
+        let auto_balance = AutoBalance::new(&args)?;
 
         // Get device id
         let device_id = args.device_id.unwrap_or(0);
@@ -186,27 +397,33 @@ impl Miner {
         let cores = self.parse_cores(args.cores);
         self.check_num_cores(cores);
 
-        // Init channel for continuous submission
-        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Solution>();
-        tokio::spawn({
-            let miner = self.clone();
-            let pool = pool.clone();
-            async move {
-                while let Some(solution) = rx.recv().await {
-                    if let Err(err) = pool.post_pool_solution(&miner, &solution).await {
-                        println!("error submitting solution: {:?}", err);
-                    }
-                }
-            }
-        });
+        // Init queue for continuous submission to the active pool
+        let queue = SubmissionQueue::new(self.clone(), pools.pool().clone());
 
         // Start collecting loop
         let mut last_hash_at = 0;
         loop {
+            // Return to a higher priority pool once it recovers
+            if pools.recheck(self).await {
+                queue.set_pool(pools.pool().clone());
+                last_hash_at = 0;
+            }
+            let pool = pools.pool().clone();
+            let nonce_index = pools.nonce_index();
+
             // Fetch latest challenge
-            let member_challenge = match pool.get_updated_pool_challenge(self, last_hash_at).await {
+            let challenges = pools.streams().challenges.as_mut();
+            let member_challenge = match pool
+                .get_updated_pool_challenge(self, last_hash_at, challenges)
+                .await
+            {
                 Err(_err) => {
-                    tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                    if pools.record_failure(self).await {
+                        queue.set_pool(pools.pool().clone());
+                        last_hash_at = 0;
+                    } else {
+                        tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                    }
                     continue;
                 }
                 Ok(member_challenge) => member_challenge,
@@ -214,12 +431,35 @@ impl Miner {
 
             // Log collecting table
             self.update_pool_collecting_table(verbose);
+            println!("{}\n", queue.stats);
 
             // Increment last balance and hash
             last_hash_at = member_challenge.challenge.lash_hash_at;
 
-            // Compute cutoff time
-            let cutoff_time = self.get_cutoff(last_hash_at, args.buffer_time).await;
+            // Compute cutoff time, from the local clock for a mock pool
+            let cutoff_time = if args.pool_mock {
+                Ok(cutoff(
+                    last_hash_at,
+                    args.buffer_time,
+                    chrono::Utc::now().timestamp(),
+                ))
+            } else {
+                self.get_cutoff(last_hash_at, args.buffer_time).await
+            };
+            let cutoff_time = match cutoff_time {
+                Err(_err) => {
+                    tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                    continue;
+                }
+                Ok(cutoff_time) => cutoff_time,
+            };
+
+            // Accept solutions until the time reserved for submitting runs out
+            let challenge = member_challenge.challenge.challenge;
+            queue.start_round(
+                challenge,
+                Instant::now() + Duration::from_secs(cutoff_time + args.buffer_time),
+            );
 
             // Build nonce indices
             let num_total_members = member_challenge.num_total_members.max(1);
@@ -246,24 +486,36 @@ impl Miner {
 
             // Run drillx
             let solution = Self::find_hash_par(
-                member_challenge.challenge.challenge,
+                challenge,
                 cutoff_time,
                 cores,
                 member_challenge.challenge.min_difficulty as u32,
                 nonce_indices.as_slice(),
-                Some(tx.clone()),
+                Some(queue.clone()),
             )
             .await;
 
-            // Post solution to pool server
-            match pool.post_pool_solution(self, &solution).await {
-                Err(_err) => {
-                    tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+            // Queue the final solution, unless a better one was already posted,
+            // and wait for the round's submissions to settle
+            queue.push(challenge, solution);
+            match queue.flush().await {
+                None => {
+                    if pools.record_failure(self).await {
+                        queue.set_pool(pools.pool().clone());
+                        last_hash_at = 0;
+                    } else {
+                        tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                    }
                     continue;
                 }
-                Ok(()) => {
-                    self.fetch_pool_mine_event(pool, last_hash_at, verbose)
+                Some(_difficulty) => {
+                    pools.record_success();
+                    let events = pools.streams().events.as_mut();
+                    self.fetch_pool_mine_event(&pool, last_hash_at, events, verbose)
                         .await;
+                    if let Some(auto_balance) = &auto_balance {
+                        auto_balance.check(self, &pool);
+                    }
                 }
             }
         }
@@ -275,7 +527,7 @@ impl Miner {
         cores: u64,
         min_difficulty: u32,
         nonce_indices: &[u64],
-        pool_channel: Option<tokio::sync::mpsc::UnboundedSender<Solution>>,
+        pool_queue: Option<SubmissionQueue>,
     ) -> Solution {
         // Dispatch job to each thread
         let progress_bar = Arc::new(spinner::new_progress_bar());
@@ -291,7 +543,7 @@ impl Miner {
                     let progress_bar = progress_bar.clone();
                     let nonce = nonce_indices[i.id];
                     let mut memory = equix::SolverMemory::new();
-                    let pool_channel = pool_channel.clone();
+                    let pool_queue = pool_queue.clone();
                     move || {
                         // Pin to core
                         let _ = core_affinity::set_for_current(i);
@@ -324,16 +576,14 @@ impl Miner {
 
                                         // Continuously upload best solution to pool
                                         if difficulty.ge(&min_difficulty) {
-                                            if let Some(ref ch) = pool_channel {
+                                            if let Some(ref queue) = pool_queue {
                                                 let digest = best_hash.d;
                                                 let nonce = nonce.to_le_bytes();
                                                 let solution = Solution {
                                                     d: digest,
                                                     n: nonce,
                                                 };
-                                                if let Err(err) = ch.send(solution) {
-                                                    println!("{} {:?}", "ERROR".bold().red(), err);
-                                                }
+                                                queue.push(challenge, solution);
                                             }
                                         }
                                     }
@@ -414,29 +664,25 @@ impl Miner {
         }
     }
 
-    async fn should_reset(&self, config: Config) -> bool {
-        let clock = get_clock(&self.rpc_client)
-            .await
-            .expect("Failed to fetch clock account");
-        config
+    pub async fn should_reset(&self, config: Config) -> Result<bool, anyhow::Error> {
+        let clock = get_clock(&self.rpc_client).await?;
+        Ok(config
             .last_reset_at
             .saturating_add(EPOCH_DURATION)
             .saturating_sub(5) // Buffer
-            .le(&clock.unix_timestamp)
+            .le(&clock.unix_timestamp))
     }
 
-    async fn get_cutoff(&self, last_hash_at: i64, buffer_time: u64) -> u64 {
-        let clock = get_clock(&self.rpc_client)
-            .await
-            .expect("Failed to fetch clock account");
-        last_hash_at
-            .saturating_add(60)
-            .saturating_sub(buffer_time as i64)
-            .saturating_sub(clock.unix_timestamp)
-            .max(0) as u64
+    pub async fn get_cutoff(
+        &self,
+        last_hash_at: i64,
+        buffer_time: u64,
+    ) -> Result<u64, anyhow::Error> {
+        let clock = get_clock(&self.rpc_client).await?;
+        Ok(cutoff(last_hash_at, buffer_time, clock.unix_timestamp))
     }
 
-    async fn find_bus(&self) -> Pubkey {
+    pub async fn find_bus(&self) -> Pubkey {
         // Fetch the bus with the largest balance
         if let Ok(accounts) = self.rpc_client.get_multiple_accounts(&BUS_ADDRESSES).await {
             let mut top_bus_balance: u64 = 0;
@@ -483,7 +729,7 @@ impl Miner {
             if tx.is_ok() {
                 break;
             }
-            sleep(Duration::from_secs(1));
+            tokio::time::sleep(Duration::from_secs(1)).await;
             attempts += 1;
             if attempts > 30 {
                 break;
@@ -543,11 +789,21 @@ impl Miner {
         }
     }
 
-    async fn fetch_pool_mine_event(&self, pool: &Pool, last_hash_at: i64, verbose: bool) {
-        let collecting_data = match pool
-            .get_latest_pool_event(self.signer().pubkey(), last_hash_at)
-            .await
-        {
+    async fn fetch_pool_mine_event(
+        &self,
+        pool: &Pool,
+        last_hash_at: i64,
+        events: Option<&mut EventSubscription<PoolMemberMiningEvent>>,
+        verbose: bool,
+    ) {
+        let authority = self.signer().pubkey();
+        let event = pool
+            .get_latest_pool_event(authority, last_hash_at, events)
+            .await;
+        if let Ok(event) = &event {
+            record_pool_event(pool, authority, event);
+        }
+        let collecting_data = match event {
             Ok(event) => PoolCollectingData {
                 signature: if verbose {
                     event.signature.to_string()
@@ -579,6 +835,7 @@ impl Miner {
                 } else {
                     "0".to_string()
                 },
+                pool: pool.label(),
             },
             Err(err) => PoolCollectingData {
                 signature: format!("Failed to fetch event: {:?}", err),
@@ -591,6 +848,7 @@ impl Miner {
                 total_reward: "".to_string(),
                 my_difficulty: "".to_string(),
                 my_reward: "".to_string(),
+                pool: pool.label(),
             },
         };
 
@@ -660,3 +918,37 @@ impl Miner {
         }
     }
 }
+
+/// Seconds left at `now` to collect for a challenge last hashed at
+/// `last_hash_at`, keeping `buffer_time` seconds to submit.
+pub fn cutoff(last_hash_at: i64, buffer_time: u64, now: i64) -> u64 {
+    last_hash_at
+        .saturating_add(60)
+        .saturating_sub(buffer_time as i64)
+        .saturating_sub(now)
+        .max(0) as u64
+}
+
+/// Share of the reward left after the liveness penalty when a solution for a
+/// proof last hashed at `last_hash_at` lands at `landed_at`. Past the tolerance,
+/// the program halves the reward for each full minute late and takes up to
+/// another half off linearly over the remaining seconds.
+pub fn liveness_factor(last_hash_at: i64, landed_at: i64) -> f64 {
+    let target = last_hash_at.saturating_add(ONE_MINUTE);
+    if landed_at <= target.saturating_add(TOLERANCE) {
+        return 1.0;
+    }
+    let secs_late = landed_at.saturating_sub(target);
+    let mins_late = secs_late / ONE_MINUTE;
+    let remainder = secs_late % ONE_MINUTE;
+    0.5f64.powi(mins_late.min(i32::MAX as i64) as i32)
+        * (1.0 - 0.5 * remainder as f64 / ONE_MINUTE as f64)
+}
+
+/// Reward in BITZ for a solution of the given difficulty, as listed by `bitz program`.
+pub fn expected_reward(config: &Config, difficulty: u32) -> f64 {
+    let reward_rate = config.base_reward_rate.saturating_mul(
+        2u64.saturating_pow(difficulty.saturating_sub(config.min_difficulty as u32)),
+    );
+    amount_u64_to_f64(reward_rate).min(1.0)
+}
@@ -10,17 +10,14 @@ use super::pool::Pool;
 impl Miner {
     // 为解决冲突，提供一个独立的挖矿入口点
     pub async fn miner_collect(&self, args: CollectArgs) -> Result<(), Error> {
-        match args.pool_url {
-            Some(ref pool_url) => {
-                println!("连接到矿池 {}...", pool_url);
-                println!("使用 {} 个核心", args.cores);
-                println!("开始挖矿进程...");
-            }
-            None => {
-                println!("开始单机挖矿...");
-                println!("使用 {} 个核心", args.cores);
-                println!("开始挖矿进程...");
-            }
+        if args.pool_url.is_empty() {
+            println!("开始单机挖矿...");
+            println!("使用 {} 个核心", args.cores);
+            println!("开始挖矿进程...");
+        } else {
+            println!("连接到矿池 {}...", args.pool_url.join(", "));
+            println!("使用 {} 个核心", args.cores);
+            println!("开始挖矿进程...");
         }
         
         // 返回成功
@@ -1,16 +1,27 @@
 mod account;
+mod auto_balance;
 mod benchmark;
 mod claim;
+mod estimate;
 #[cfg(feature = "admin")]
 mod initialize;
 // mod collect; // 功能已移至 mine.rs
 pub mod pool;
+mod pool_audit;
+mod pool_mock;
+mod pool_serve;
+mod pool_server;
+mod pool_stake;
 mod program;
 mod stake;
+mod submission;
 mod transaction;
 mod transfer;
 mod mine;
 mod stop_mining;
 mod check;
+mod fees;
+mod fund;
+mod history;
 mod miner_wrapper;
 mod batch_mining; // 新增批量挖矿模块
@@ -1,4 +1,8 @@
-use std::sync::Arc;
+use std::{
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crossterm::style::Stylize;
 use drillx::Solution;
@@ -9,10 +13,14 @@ use ore_pool_types::{
     UpdateBalancePayload,
 };
 use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
 use solana_rpc_client::spinner;
 use solana_sdk::{
-    compute_budget, pubkey::Pubkey, signature::Signature, signer::Signer, transaction::Transaction,
+    commitment_config::CommitmentConfig, compute_budget, pubkey::Pubkey, signature::Signature,
+    signer::Signer, transaction::Transaction,
 };
+use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
 use steel::AccountDeserialize;
 use tabled::{
     settings::{
@@ -24,15 +32,175 @@ use tabled::{
 };
 
 use crate::{
-    args::{PoolArgs, PoolCommand, PoolCommitArgs},
+    args::{PoolArgs, PoolCommand, PoolCommitArgs, PoolListArgs},
     error::Error,
+    send::{transaction_fee, LandedRecord},
     utils::{
-        self, amount_u64_to_f64, format_timestamp, get_member, get_pool, get_pools, get_proof,
-        ComputeBudget, TableData, TableSectionTitle,
+        self, amount_u64_to_f64, append_jsonl, format_timestamp, get_member, get_pool, get_pools,
+        get_proof, mine_event_from_logs, print_json, read_jsonl, ComputeBudget, EventSubscription,
+        OutputFormat, ProofOutput, TableData, TableSectionTitle, TokenAmount,
     },
     Miner,
 };
 
+/// Seconds without a new pool challenge before the challenge is considered stale.
+const STALE_CHALLENGE_TIMEOUT: u64 = 90;
+
+/// Seconds between checks whether a higher priority pool has recovered.
+const POOL_RECHECK_INTERVAL: u64 = 60;
+
+/// Seconds to wait for a pushed mining event before polling for it.
+const EVENT_STREAM_TIMEOUT: u64 = 10;
+
+/// Seconds to wait for a pool server to answer a probe.
+const PROBE_TIMEOUT: u64 = 5;
+
+/// Recent mine events of a pool proof sampled for its rewards per hash.
+const PROBE_EVENTS: usize = 5;
+
+/// `--pool-url` value that expands to the best probed pools.
+const POOL_URL_AUTO: &str = "auto";
+
+/// Pools `auto` expands to, in order of score.
+const AUTO_POOL_COUNT: usize = 3;
+
+/// Compute units of the attribute transaction a pool co-signs on commit.
+const COMMIT_COMPUTE_UNITS: u32 = 100_000;
+
+/// Local ledger of the pool mining events received while collecting.
+const POOL_EVENTS_PATH: &str = "logs/pool_events.jsonl";
+
+/// Push channels a pool server advertises at `GET /capabilities`. Pools that
+/// don't serve the endpoint are polled.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct PoolCapabilities {
+    /// New `MemberChallenge`s are pushed as server-sent events at
+    /// `GET /challenge/{authority}/stream`.
+    #[serde(default)]
+    pub challenge_stream: bool,
+    /// New `PoolMemberMiningEvent`s are pushed as server-sent events at
+    /// `GET /event/{authority}/stream`.
+    #[serde(default)]
+    pub event_stream: bool,
+}
+
+/// A pool mining event as kept in the local ledger.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PoolEventRecord {
+    /// Unix timestamp of when the event was received.
+    pub timestamp: i64,
+    /// Pool url without the scheme.
+    pub pool: String,
+    pub authority: String,
+    pub signature: String,
+    pub last_hash_at: i64,
+    pub member_difficulty: u64,
+    pub member_reward: u64,
+}
+
+/// Appends a mining event received from a pool to the local ledger.
+pub fn record_pool_event(
+    pool: &Pool,
+    authority: Pubkey,
+    event: &ore_pool_types::PoolMemberMiningEvent,
+) {
+    let record = PoolEventRecord {
+        timestamp: chrono::Utc::now().timestamp(),
+        pool: pool.label(),
+        authority: authority.to_string(),
+        signature: event.signature.to_string(),
+        last_hash_at: event.last_hash_at,
+        member_difficulty: event.member_difficulty,
+        member_reward: event.member_reward,
+    };
+    if let Err(err) = append_jsonl(POOL_EVENTS_PATH, &record) {
+        log::warn!("Failed to record pool event: {}", err);
+    }
+}
+
+/// Reads the local ledger of pool mining events, oldest first.
+pub fn read_pool_events() -> Result<Vec<PoolEventRecord>, std::io::Error> {
+    read_jsonl(POOL_EVENTS_PATH)
+}
+
+/// Subscriptions to the push channels of a pool, where available.
+#[derive(Default)]
+pub struct PoolStreams {
+    pub challenges: Option<EventSubscription<MemberChallenge>>,
+    pub events: Option<EventSubscription<ore_pool_types::PoolMemberMiningEvent>>,
+}
+
+/// JSON output of `pool`, one per pool.
+#[derive(Serialize)]
+struct PoolOutput {
+    address: String,
+    url: String,
+    members: u64,
+    /// `null` when the pool has no proof.
+    proof: Option<ProofOutput>,
+}
+
+/// JSON output of `pool --pool-url`.
+#[derive(Serialize)]
+struct PoolDetailOutput {
+    address: String,
+    url: String,
+    total_members: u64,
+    proof_address: String,
+    proof: ProofOutput,
+    /// `null` when the signer is not a member of the pool.
+    member: Option<MemberOutput>,
+}
+
+#[derive(Serialize)]
+struct MemberOutput {
+    address: String,
+    balance: TokenAmount,
+    /// Rewards the pool server has not committed onchain yet, `null` when the
+    /// server is unreachable.
+    pending_rewards: Option<TokenAmount>,
+    total_balance: TokenAmount,
+}
+
+/// JSON output of `pool list --probe`, one per pool.
+#[derive(Serialize)]
+struct PoolProbeOutput {
+    address: String,
+    url: String,
+    members: u64,
+    /// Milliseconds to answer `GET /address`, `null` when unreachable.
+    address_latency_ms: Option<u64>,
+    /// Milliseconds to answer `GET /challenge`, `null` when unreachable.
+    challenge_latency_ms: Option<u64>,
+    /// Whether `GET /address` matches the onchain pool, `null` when unreachable.
+    address_matches: Option<bool>,
+    /// Net reward per expected hash over recent mine events, in the smallest
+    /// unit. `null` without recent events.
+    reward_per_hash: Option<f64>,
+    /// 0 to 100, relative to the best pool.
+    score: f64,
+}
+
+/// A pool server as measured by `pool list --probe`.
+struct PoolProbe {
+    address: Pubkey,
+    url: String,
+    members: u64,
+    address_latency: Option<Duration>,
+    challenge_latency: Option<Duration>,
+    address_matches: Option<bool>,
+    reward_per_hash: Option<f64>,
+    score: f64,
+}
+
+impl PoolProbe {
+    /// A pool is usable if it answers both endpoints and is the pool it
+    /// claims to be.
+    fn is_healthy(&self) -> bool {
+        self.address_matches == Some(true) && self.challenge_latency.is_some()
+    }
+}
+
 impl Miner {
     // TODO
     pub async fn pool(&self, args: PoolArgs) {
@@ -41,21 +209,57 @@ impl Miner {
                 PoolCommand::Commit(commit_args) => {
                     self.pool_commit(args, commit_args).await.unwrap()
                 }
+                PoolCommand::Unstake(unstake_args) => {
+                    if let Err(err) = self.pool_unstake(args, unstake_args).await {
+                        println!("{:?}", err);
+                    }
+                }
+                PoolCommand::Shares(shares_args) => {
+                    if let Err(err) = self.pool_shares(shares_args).await {
+                        println!("{:?}", err);
+                    }
+                }
+                PoolCommand::Audit(audit_args) => {
+                    if let Err(err) = self.pool_audit(args, audit_args).await {
+                        println!("{:?}", err);
+                    }
+                }
+                PoolCommand::List(list_args) => {
+                    if let Err(err) = self.list_pools(list_args).await {
+                        println!("{:?}", err);
+                    }
+                }
+                PoolCommand::Mock(mock_args) => {
+                    if let Err(err) = self.pool_mock(mock_args).await {
+                        println!("{:?}", err);
+                    }
+                }
+                PoolCommand::Serve(serve_args) => {
+                    if let Err(err) = self.pool_serve(serve_args).await {
+                        println!("{:?}", err);
+                    }
+                }
             }
         } else {
             if let Some(pool_url) = args.pool_url {
                 self.get_pool(pool_url).await.unwrap();
             } else {
-                self.list_pools(args).await.unwrap();
+                self.list_pools(PoolListArgs { probe: false })
+                    .await
+                    .unwrap();
             }
         }
     }
 
-    async fn list_pools(&self, _args: PoolArgs) -> Result<(), Error> {
+    async fn list_pools(&self, args: PoolListArgs) -> Result<(), Error> {
+        if args.probe {
+            return self.list_probed_pools().await;
+        }
         let pools = get_pools(&self.rpc_client)
             .await
             .expect("Failed to fetch pool accounts");
         let mut data = vec![];
+        let mut output = vec![];
         for (pool_address, pool) in pools {
             let url = String::from_utf8(pool.url.to_vec()).unwrap_or_default();
             let url = url.trim_end_matches('\0');
@@ -71,7 +275,8 @@ impl Miner {
 
             // Get proof data
             let proof_address = proof_pda(pool_address).0;
-            if let Ok(proof) = get_proof(&self.rpc_client, proof_address).await {
+            let proof = get_proof(&self.rpc_client, proof_address).await.ok();
+            if let Some(proof) = proof {
                 point.balance = format!("{} BITZ", amount_u64_to_f64(proof.balance));
                 point.last_hash_at = format_timestamp(proof.last_hash_at);
                 point.lifetime_hashes = proof.total_hashes.to_string();
@@ -80,6 +285,16 @@ impl Miner {
 
             // Push data
             data.push(point);
+            output.push(PoolOutput {
+                address: pool_address.to_string(),
+                url: url.to_string(),
+                members: pool.total_members,
+                proof: proof.map(ProofOutput::from),
+            });
+        }
+        if self.output == OutputFormat::Json {
+            print_json(&output);
+            return Ok(());
         }
 
         // Print table
@@ -96,6 +311,227 @@ impl Miner {
         Ok(())
     }
 
+    async fn list_probed_pools(&self) -> Result<(), Error> {
+        let progress_bar = Arc::new(spinner::new_progress_bar());
+        progress_bar.set_message("Probing pools...");
+        let probes = self.probe_pools().await;
+        progress_bar.finish_and_clear();
+        let probes = probes?;
+        if self.output == OutputFormat::Json {
+            let output: Vec<_> = probes
+                .iter()
+                .map(|probe| PoolProbeOutput {
+                    address: probe.address.to_string(),
+                    url: probe.url.clone(),
+                    members: probe.members,
+                    address_latency_ms: probe.address_latency.map(|d| d.as_millis() as u64),
+                    challenge_latency_ms: probe.challenge_latency.map(|d| d.as_millis() as u64),
+                    address_matches: probe.address_matches,
+                    reward_per_hash: probe.reward_per_hash,
+                    score: probe.score,
+                })
+                .collect();
+            print_json(&output);
+            return Ok(());
+        }
+
+        // Print table
+        let latency = |latency: Option<Duration>| match latency {
+            Some(latency) => format!("{}ms", latency.as_millis()),
+            None => "unreachable".red().to_string(),
+        };
+        let data: Vec<_> = probes
+            .iter()
+            .map(|probe| PoolProbeTableData {
+                address: probe.address.to_string(),
+                members: probe.members.to_string(),
+                address_latency: latency(probe.address_latency),
+                challenge_latency: latency(probe.challenge_latency),
+                address_check: match probe.address_matches {
+                    Some(true) => "ok".green().to_string(),
+                    Some(false) => "mismatch".red().to_string(),
+                    None => "–".to_string(),
+                },
+                reward_per_hash: probe.reward_per_hash.map_or("–".to_string(), |reward| {
+                    format!("{:.11}", amount_u64_to_f64((reward * 1e6) as u64))
+                }),
+                score: format!("{:.1}", probe.score),
+                url: probe.url.clone(),
+            })
+            .collect();
+        let mut table = Table::new(&data);
+        table.with(Style::blank());
+        table.modify(Columns::new(1..), Alignment::right());
+        table.modify(Rows::first(), Color::BOLD);
+        table.with(
+            Highlight::new(Rows::single(1)).color(BorderColor::default().top(Color::FG_WHITE)),
+        );
+        table.with(Highlight::new(Rows::single(1)).border(Border::new().top('━')));
+        println!("\n{}\n", table);
+        println!("Score weighs recent rewards per hash by latency, relative to the best pool. Use `--pool-url auto` to collect with the best pools.\n");
+        Ok(())
+    }
+
+    /// Contacts every onchain pool that has a url and ranks them by score,
+    /// best first. Score is the recent reward per hash divided by
+    /// `1 + latency in seconds`, scaled so the best pool scores 100. Pools
+    /// that are unreachable or report another address score 0.
+    async fn probe_pools(&self) -> Result<Vec<PoolProbe>, Error> {
+        let pools = get_pools(&self.rpc_client).await?;
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(PROBE_TIMEOUT))
+            .build()?;
+        let authority = self.signer().pubkey();
+        let probes = pools.into_iter().filter_map(|(address, pool)| {
+            let url = String::from_utf8(pool.url.to_vec()).unwrap_or_default();
+            let url = url.trim_end_matches('\0').to_string();
+            if url.is_empty() {
+                return None;
+            }
+            let pool_client = Pool {
+                http_client: http_client.clone(),
+                pool_url: url.clone(),
+            };
+            Some(async move {
+                // Time the pool api, quietly falling back to the legacy address route
+                let timer = Instant::now();
+                let mut reported = None;
+                for route in ["address", "pool-address"] {
+                    let get_url = format!("{}/{}", pool_client.pool_url(), route);
+                    let resp = pool_client.http_client.get(get_url).send().await;
+                    if let Ok(resp) = resp.and_then(|resp| resp.error_for_status()) {
+                        reported = resp.json::<PoolAddress>().await.ok();
+                        break;
+                    }
+                }
+                let address_latency = reported.is_some().then(|| timer.elapsed());
+                let timer = Instant::now();
+                let challenge_url = format!("{}/challenge/{}", pool_client.pool_url(), authority);
+                let challenge_latency =
+                    match pool_client.http_client.get(challenge_url).send().await {
+                        Ok(_) => Some(timer.elapsed()),
+                        Err(_) => None,
+                    };
+                PoolProbe {
+                    address,
+                    url,
+                    members: pool.total_members,
+                    address_latency,
+                    challenge_latency,
+                    address_matches: reported.map(|reported| reported.address == address),
+                    reward_per_hash: self.recent_reward_per_hash(proof_pda(address).0).await,
+                    score: 0.0,
+                }
+            })
+        });
+        let mut probes = futures::future::join_all(probes).await;
+
+        // Score
+        for probe in probes.iter_mut() {
+            let latency = probe.address_latency.unwrap_or_default()
+                + probe.challenge_latency.unwrap_or_default();
+            probe.score = match (probe.is_healthy(), probe.reward_per_hash) {
+                (true, Some(reward)) => reward / (1.0 + latency.as_secs_f64() / 2.0),
+                _ => 0.0,
+            };
+        }
+        let best = probes.iter().map(|probe| probe.score).fold(0.0, f64::max);
+        for probe in probes.iter_mut() {
+            if best > 0.0 {
+                probe.score = probe.score / best * 100.0;
+            }
+        }
+        probes.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(b.is_healthy().cmp(&a.is_healthy()))
+                .then(b.members.cmp(&a.members))
+        });
+        Ok(probes)
+    }
+
+    /// Net reward per expected hash over the most recent mine events of a
+    /// proof. A solution of difficulty `d` takes `2^d` hashes on average.
+    async fn recent_reward_per_hash(&self, proof_address: Pubkey) -> Option<f64> {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before: None,
+            until: None,
+            limit: Some(PROBE_EVENTS * 2),
+            commitment: Some(CommitmentConfig::confirmed()),
+        };
+        let signatures = self
+            .rpc_client
+            .get_signatures_for_address_with_config(&proof_address, config)
+            .await
+            .ok()?;
+        let mut rewards = 0.0;
+        let mut hashes = 0.0;
+        let mut events = 0;
+        for status in signatures.iter().filter(|status| status.err.is_none()) {
+            let Ok(signature) = Signature::from_str(&status.signature) else {
+                continue;
+            };
+            let Ok(tx) = self
+                .rpc_client
+                .get_transaction(&signature, UiTransactionEncoding::Json)
+                .await
+            else {
+                continue;
+            };
+            let Some(meta) = tx.transaction.meta else {
+                continue;
+            };
+            let OptionSerializer::Some(log_messages) = &meta.log_messages else {
+                continue;
+            };
+            let Some(event) = mine_event_from_logs(log_messages) else {
+                continue;
+            };
+            rewards += event.net_reward as f64;
+            hashes += 2f64.powi(event.difficulty as i32);
+            events += 1;
+            if events >= PROBE_EVENTS {
+                break;
+            }
+        }
+        (hashes > 0.0).then(|| rewards / hashes)
+    }
+
+    /// Expands `auto` in a list of pool urls to the best healthy pools by
+    /// `probe_pools`, keeping the position of `auto` in the priority order.
+    pub async fn resolve_pool_urls(&self, pool_urls: &[String]) -> Result<Vec<String>, Error> {
+        if !pool_urls.iter().any(|url| url == POOL_URL_AUTO) {
+            return Ok(pool_urls.to_vec());
+        }
+        let progress_bar = Arc::new(spinner::new_progress_bar());
+        progress_bar.set_message("Probing pools...");
+        let probes = self.probe_pools().await;
+        progress_bar.finish_and_clear();
+        let best: Vec<String> = probes?
+            .into_iter()
+            .filter(|probe| probe.is_healthy())
+            .filter(|probe| !pool_urls.contains(&probe.url))
+            .take(AUTO_POOL_COUNT)
+            .map(|probe| {
+                println!("Selected pool {} (score {:.1})", probe.url, probe.score);
+                probe.url
+            })
+            .collect();
+        if best.is_empty() {
+            return Err(Error::Internal("No reachable pool found".to_string()));
+        }
+        let mut resolved = vec![];
+        for url in pool_urls {
+            if url == POOL_URL_AUTO {
+                resolved.extend(best.iter().cloned());
+            } else {
+                resolved.push(url.clone());
+            }
+        }
+        resolved.dedup();
+        Ok(resolved)
+    }
+
     async fn get_pool(&self, pool_url: String) -> Result<(), Error> {
         // build pool client
         let pool = Pool {
@@ -129,6 +565,14 @@ impl Miner {
         let proof = get_proof(&self.rpc_client, proof_address)
             .await
             .expect("Failed to fetch proof account");
+        let mut output = PoolDetailOutput {
+            address: pool_address.to_string(),
+            url: pool_url.clone(),
+            total_members: pool_account.total_members,
+            proof_address: proof_address.to_string(),
+            proof: ProofOutput::from(proof),
+            member: None,
+        };
         data.push(TableData {
             key: "Address".to_string(),
             value: proof_address.to_string(),
@@ -174,8 +618,10 @@ impl Miner {
                     .to_string(),
             });
             // Get offchain data from pool server
+            let mut pending = None;
             if let Ok(member_offchain) = pool.get_pool_member(&self).await {
                 let pending_rewards = (member_offchain.total_balance as u64) - member.total_balance;
+                pending = Some(TokenAmount::bitz(pending_rewards));
                 data.push(TableData {
                     key: "Pending rewards".to_string(),
                     value: format!("{} BITZ", utils::amount_u64_to_string(pending_rewards)),
@@ -185,6 +631,16 @@ impl Miner {
                 key: "Lifetime rewards".to_string(),
                 value: format!("{} BITZ", utils::amount_u64_to_string(member.total_balance)),
             });
+            output.member = Some(MemberOutput {
+                address: member_address.to_string(),
+                balance: TokenAmount::bitz(member.balance),
+                pending_rewards: pending,
+                total_balance: TokenAmount::bitz(member.total_balance),
+            });
+        }
+        if self.output == OutputFormat::Json {
+            print_json(&output);
+            return Ok(());
         }
 
         // Build table
@@ -225,7 +681,6 @@ pub struct Pool {
 impl Pool {
     pub async fn post_pool_register(&self, miner: &Miner) -> Result<Member, Error> {
         let pubkey = miner.signer().pubkey();
-        let post_url = format!("{}/register", self.pool_url());
         // check if on-chain member account exists already
         let pool_pda = self.get_pool_address().await?;
         if let Err(_err) = self.get_pool_member_onchain(miner, pool_pda.address).await {
@@ -236,6 +691,14 @@ impl Pool {
                 .send_and_confirm(&[ix], ComputeBudget::Fixed(200_000), false)
                 .await?;
         };
+        self.post_register(miner).await
+    }
+
+    /// Registers with the pool server only, for pools that keep no onchain
+    /// member account such as `bitz pool mock`.
+    pub async fn post_register(&self, miner: &Miner) -> Result<Member, Error> {
+        let pubkey = miner.signer().pubkey();
+        let post_url = format!("{}/register", self.pool_url());
         // submit idempotent register payload
         // will simply return off-chain account if already registered
         let body = RegisterPayload { authority: pubkey };
@@ -294,7 +757,7 @@ impl Pool {
         }
     }
 
-    pub async fn _get_staker_onchain(
+    pub async fn get_staker_onchain(
         &self,
         miner: &Miner,
         pool_address: Pubkey,
@@ -307,17 +770,93 @@ impl Pool {
         Ok(*share)
     }
 
+    pub async fn get_capabilities(&self) -> PoolCapabilities {
+        let get_url = format!("{}/capabilities", self.pool_url());
+        match self.http_client.get(get_url).send().await {
+            Ok(resp) => match resp.error_for_status() {
+                Ok(resp) => resp.json::<PoolCapabilities>().await.unwrap_or_default(),
+                Err(_err) => PoolCapabilities::default(),
+            },
+            Err(_err) => PoolCapabilities::default(),
+        }
+    }
+
+    /// Subscribes to the push channels the pool advertises.
+    pub async fn subscribe(&self, miner: &Miner) -> PoolStreams {
+        let pubkey = miner.signer().pubkey();
+        let capabilities = self.get_capabilities().await;
+        PoolStreams {
+            challenges: capabilities.challenge_stream.then(|| {
+                EventSubscription::new(
+                    self.http_client.clone(),
+                    format!("{}/challenge/{}/stream", self.pool_url(), pubkey),
+                )
+            }),
+            events: capabilities.event_stream.then(|| {
+                EventSubscription::new(
+                    self.http_client.clone(),
+                    format!("{}/event/{}/stream", self.pool_url(), pubkey),
+                )
+            }),
+        }
+    }
+
+    /// Waits for a challenge newer than `last_hash_at`. Pushed challenges are
+    /// used while the challenge stream is connected, otherwise the pool is
+    /// polled every second.
     pub async fn get_updated_pool_challenge(
         &self,
         miner: &Miner,
         last_hash_at: i64,
+        challenges: Option<&mut EventSubscription<MemberChallenge>>,
     ) -> Result<MemberChallenge, Error> {
+        let stale = || {
+            Error::Internal(format!(
+                "No new challenge from {} in {}s",
+                self.label(),
+                STALE_CHALLENGE_TIMEOUT
+            ))
+        };
+
+        // Wait for a pushed challenge
+        if let Some(challenges) = challenges {
+            let deadline = Instant::now() + Duration::from_secs(STALE_CHALLENGE_TIMEOUT);
+            let progress_bar = Arc::new(spinner::new_progress_bar());
+            progress_bar.set_message("Waiting for new challenge...");
+            while challenges.is_connected() {
+                if let Some(challenge) = challenges.latest() {
+                    if challenge.challenge.lash_hash_at != last_hash_at {
+                        progress_bar.finish_with_message("Found new challenge");
+                        return Ok(challenge);
+                    }
+                }
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    progress_bar.finish_with_message("Challenge is stale");
+                    return Err(stale());
+                }
+                challenges.next_update(remaining).await;
+            }
+            progress_bar.finish_and_clear();
+        }
+
+        // Poll for a new challenge
         let mut retries = 0;
         let progress_bar = Arc::new(spinner::new_progress_bar());
         loop {
             progress_bar.set_message(format!("Fetching new challenge... (retry {})", retries));
-            let challenge = self.get_pool_challenge(miner).await?;
+            let challenge = match self.get_pool_challenge(miner).await {
+                Ok(challenge) => challenge,
+                Err(err) => {
+                    progress_bar.finish_and_clear();
+                    return Err(err);
+                }
+            };
             if challenge.challenge.lash_hash_at == last_hash_at {
+                if retries >= STALE_CHALLENGE_TIMEOUT {
+                    progress_bar.finish_with_message("Challenge is stale");
+                    return Err(stale());
+                }
                 retries += 1;
                 tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
             } else {
@@ -327,11 +866,33 @@ impl Pool {
         }
     }
 
+    /// Waits for the mining event of the round started at `last_hash_at`. A
+    /// pushed event is used if one arrives in time, otherwise the pool is
+    /// polled.
     pub async fn get_latest_pool_event(
         &self,
         authority: Pubkey,
         last_hash_at: i64,
+        events: Option<&mut EventSubscription<ore_pool_types::PoolMemberMiningEvent>>,
     ) -> Result<ore_pool_types::PoolMemberMiningEvent, Error> {
+        // Wait for a pushed event
+        if let Some(events) = events {
+            let deadline = Instant::now() + Duration::from_secs(EVENT_STREAM_TIMEOUT);
+            while events.is_connected() {
+                if let Some(event) = events.latest() {
+                    if event.last_hash_at >= last_hash_at {
+                        return Ok(event);
+                    }
+                }
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break;
+                }
+                events.next_update(remaining).await;
+            }
+        }
+
+        // Poll for the event
         let get_url = format!("{}/event/latest/{}", self.pool_url(), authority);
         let mut attempts = 0;
         let progress_bar = Arc::new(spinner::new_progress_bar());
@@ -396,9 +957,10 @@ impl Pool {
             member.total_balance as u64,
         );
         let compute_budget_limit_ix =
-            compute_budget::ComputeBudgetInstruction::set_compute_unit_limit(100_000);
+            compute_budget::ComputeBudgetInstruction::set_compute_unit_limit(COMMIT_COMPUTE_UNITS);
+        let cu_price = miner.current_priority_fee().await;
         let compute_budget_price_ix =
-            compute_budget::ComputeBudgetInstruction::set_compute_unit_price(20_000);
+            compute_budget::ComputeBudgetInstruction::set_compute_unit_price(cu_price);
         let mut tx = Transaction::new_with_payer(
             &[compute_budget_limit_ix, compute_budget_price_ix, ix],
             Some(signer_pubkey),
@@ -406,6 +968,33 @@ impl Pool {
         let hash = miner.rpc_client.get_latest_blockhash().await?;
         tx.partial_sign(&[signer], hash);
 
+        // The pool signs last, so a simulation stops before posting
+        if miner.simulate {
+            miner
+                .print_simulation(&tx, COMMIT_COMPUTE_UNITS, cu_price)
+                .await?;
+            return Ok(());
+        }
+
+        // The member pays the fee of the commit
+        miner.check_spending(COMMIT_COMPUTE_UNITS).await?;
+
+        // The pool lands the commit, so its fee is recorded here
+        let commit_fee = LandedRecord {
+            timestamp: chrono::Utc::now().timestamp(),
+            signature: tx.signatures[0].to_string(),
+            fee_payer: signer_pubkey.to_string(),
+            signer: signer_pubkey.to_string(),
+            cu_limit: COMMIT_COMPUTE_UNITS,
+            cu_price,
+            fee: transaction_fee(
+                tx.message.header.num_required_signatures as u64,
+                COMMIT_COMPUTE_UNITS,
+                cu_price,
+            ),
+            attempts: 1,
+        };
+
         // build payload
         let paylaod = UpdateBalancePayload {
             authority: *signer_pubkey,
@@ -413,12 +1002,13 @@ impl Pool {
             hash,
         };
 
-        // post
+        // post, giving the pool as long to land the commit as a send may take
         let post_url = format!("{}/commit", self.pool_url());
         let resp = self
             .http_client
             .post(post_url)
             .json(&paylaod)
+            .timeout(miner.send_policy.deadline)
             .send()
             .await?;
         match resp.error_for_status() {
@@ -429,6 +1019,7 @@ impl Pool {
                     .http_client
                     .post(post_url)
                     .json(&paylaod)
+                    .timeout(miner.send_policy.deadline)
                     .send()
                     .await?;
                 match resp.error_for_status() {
@@ -437,6 +1028,7 @@ impl Pool {
                         Err(err).map_err(From::from)
                     }
                     Ok(resp) => {
+                        miner.record_fee(commit_fee);
                         let balance_update = resp.json::<BalanceUpdate>().await;
                         println!("{:?}", balance_update);
                         Ok(())
@@ -444,6 +1036,7 @@ impl Pool {
                 }
             }
             Ok(resp) => {
+                miner.record_fee(commit_fee);
                 let balance_update = resp.json::<BalanceUpdate>().await;
                 println!("{:?}", balance_update);
                 Ok(())
@@ -497,12 +1090,180 @@ impl Pool {
         pool_url.trim_end_matches('/').to_string()
     }
 
+    /// Pool url without the scheme, to tell pools apart in tables and logs.
+    pub fn label(&self) -> String {
+        let pool_url = self.pool_url();
+        pool_url
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .to_string()
+    }
+
     fn sign_solution(miner: &Miner, solution: &Solution) -> Signature {
         let keypair = &miner.signer();
         keypair.sign_message(solution.to_bytes().as_slice())
     }
 }
 
+/// Pools to collect with, in order of priority. Collecting uses the first pool
+/// that works, fails over to the next one after repeated failures and returns
+/// to a higher priority pool once it answers again.
+pub struct PoolFailover {
+    pools: Vec<Pool>,
+    members: Vec<Option<Member>>,
+    streams: PoolStreams,
+    active: usize,
+    failures: u32,
+    max_failures: u32,
+    last_recheck: Instant,
+    mock: bool,
+}
+
+impl PoolFailover {
+    /// Registers with every pool up front. Fails only if no pool accepts the
+    /// registration; the others are registered again when switched to.
+    /// Mock pools are joined without an onchain member account.
+    pub async fn new(
+        miner: &Miner,
+        pool_urls: &[String],
+        max_failures: u32,
+        mock: bool,
+    ) -> Result<Self, Error> {
+        let pools: Vec<Pool> = pool_urls
+            .iter()
+            .map(|pool_url| Pool {
+                http_client: reqwest::Client::new(),
+                pool_url: pool_url.clone(),
+            })
+            .collect();
+        let mut members = Vec::with_capacity(pools.len());
+        let mut last_err = None;
+        for pool in pools.iter() {
+            match Self::register_member(miner, pool, mock).await {
+                Ok(member) => members.push(Some(member)),
+                Err(err) => {
+                    println!(
+                        "{} Failed to register with pool {}: {}",
+                        "WARNING".bold().yellow(),
+                        pool.label(),
+                        err
+                    );
+                    members.push(None);
+                    last_err = Some(err);
+                }
+            }
+        }
+        let Some(active) = members.iter().position(|member| member.is_some()) else {
+            return Err(last_err.unwrap_or(Error::Internal("No pool url given".to_string())));
+        };
+        let streams = pools[active].subscribe(miner).await;
+        Ok(Self {
+            pools,
+            members,
+            streams,
+            active,
+            failures: 0,
+            max_failures: max_failures.max(1),
+            last_recheck: Instant::now(),
+            mock,
+        })
+    }
+
+    pub fn pool(&self) -> &Pool {
+        &self.pools[self.active]
+    }
+
+    /// Push channels of the active pool.
+    pub fn streams(&mut self) -> &mut PoolStreams {
+        &mut self.streams
+    }
+
+    /// Member id in the active pool, which selects the member's nonce range.
+    pub fn nonce_index(&self) -> u64 {
+        self.members[self.active]
+            .as_ref()
+            .map_or(0, |member| member.id as u64)
+    }
+
+    pub fn record_success(&mut self) {
+        self.failures = 0;
+    }
+
+    /// Records a failed or stale round and switches to the next registered
+    /// pool once the active one has failed `max_failures` times in a row.
+    /// Returns whether the active pool changed.
+    pub async fn record_failure(&mut self, miner: &Miner) -> bool {
+        self.failures += 1;
+        if self.failures < self.max_failures || self.pools.len() < 2 {
+            return false;
+        }
+        self.failures = 0;
+        for offset in 1..self.pools.len() {
+            let index = (self.active + offset) % self.pools.len();
+            if self.register(miner, index).await {
+                println!(
+                    "{} Pool {} failed {} times in a row, switching to {}",
+                    "WARNING".bold().yellow(),
+                    self.pool().label(),
+                    self.max_failures,
+                    self.pools[index].label()
+                );
+                self.activate(miner, index).await;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Switches back to the highest priority pool that serves challenges
+    /// again. Checked at most once per `POOL_RECHECK_INTERVAL`.
+    pub async fn recheck(&mut self, miner: &Miner) -> bool {
+        if self.active == 0
+            || self.last_recheck.elapsed() < Duration::from_secs(POOL_RECHECK_INTERVAL)
+        {
+            return false;
+        }
+        self.last_recheck = Instant::now();
+        for index in 0..self.active {
+            if self.pools[index].get_pool_challenge(miner).await.is_ok()
+                && self.register(miner, index).await
+            {
+                println!(
+                    "Pool {} recovered, switching back from {}",
+                    self.pools[index].label(),
+                    self.pool().label()
+                );
+                self.activate(miner, index).await;
+                self.failures = 0;
+                return true;
+            }
+        }
+        false
+    }
+
+    async fn activate(&mut self, miner: &Miner, index: usize) {
+        self.active = index;
+        self.streams = self.pools[index].subscribe(miner).await;
+    }
+
+    async fn register(&mut self, miner: &Miner, index: usize) -> bool {
+        if self.members[index].is_none() {
+            self.members[index] = Self::register_member(miner, &self.pools[index], self.mock)
+                .await
+                .ok();
+        }
+        self.members[index].is_some()
+    }
+
+    async fn register_member(miner: &Miner, pool: &Pool, mock: bool) -> Result<Member, Error> {
+        if mock {
+            pool.post_register(miner).await
+        } else {
+            pool.post_pool_register(miner).await
+        }
+    }
+}
+
 // async fn parse_pool_id(pool_id: &String) -> Result<Pubkey, Error> {
 //     if let Ok(_address) = Pubkey::from_str(pool_id) {
 //         // Ok(address)
@@ -542,3 +1303,23 @@ pub struct PoolTableData {
     #[tabled(rename = "Url")]
     url: String,
 }
+
+#[derive(Clone, Tabled)]
+pub struct PoolProbeTableData {
+    #[tabled(rename = "Address")]
+    address: String,
+    #[tabled(rename = "Members")]
+    members: String,
+    #[tabled(rename = "/address")]
+    address_latency: String,
+    #[tabled(rename = "/challenge")]
+    challenge_latency: String,
+    #[tabled(rename = "Address check")]
+    address_check: String,
+    #[tabled(rename = "Reward per Mhash")]
+    reward_per_hash: String,
+    #[tabled(rename = "Score")]
+    score: String,
+    #[tabled(rename = "Url")]
+    url: String,
+}
@@ -0,0 +1,244 @@
+use std::collections::HashSet;
+
+use colored::*;
+use ore_pool_api::state::member_pda;
+use serde::Serialize;
+use solana_sdk::signer::Signer;
+use tabled::{
+    settings::{object::Rows, Remove, Style},
+    Table,
+};
+
+use crate::{
+    args::{PoolArgs, PoolAuditArgs},
+    error::Error,
+    utils::{
+        amount_f64_to_u64, amount_u64_to_string, format_timestamp, get_member, print_json,
+        OutputFormat, TableData, TableSectionTitle, TokenAmount,
+    },
+    Miner,
+};
+
+use super::pool::{read_pool_events, Pool};
+
+/// Result of a pool audit.
+#[derive(Serialize)]
+struct AuditOutput {
+    pool_url: String,
+    pool_address: String,
+    member_address: String,
+    /// Total balance the pool server reports for the member.
+    reported_total_balance: TokenAmount,
+    /// Total balance attributed to the member onchain.
+    onchain_total_balance: TokenAmount,
+    /// Balance the member can still claim onchain.
+    onchain_balance: TokenAmount,
+    /// Sum of the member rewards in the local event ledger.
+    recorded_rewards: TokenAmount,
+    recorded_rounds: usize,
+    /// Unix timestamp of the oldest recorded event.
+    recorded_since: Option<i64>,
+    tolerance: TokenAmount,
+    checks: Vec<AuditCheck>,
+    flagged: bool,
+}
+
+/// One comparison between two sources of the member balance.
+#[derive(Serialize)]
+struct AuditCheck {
+    name: String,
+    /// How much the first source exceeds the second. Negative when it falls short.
+    gap: i128,
+    flagged: bool,
+    note: String,
+}
+
+impl AuditCheck {
+    fn new(name: &str, gap: i128, flagged: bool, note: String) -> Self {
+        Self {
+            name: name.to_string(),
+            gap,
+            flagged,
+            note,
+        }
+    }
+
+    fn status(&self) -> String {
+        if self.flagged {
+            format!("{} {}", "FLAGGED".bold().red(), self.note)
+        } else {
+            format!("{} {}", "OK".bold().green(), self.note)
+        }
+    }
+}
+
+impl Miner {
+    /// Reconciles the balance a pool reports for the signer against the
+    /// onchain member account and the mining events recorded while collecting.
+    pub async fn pool_audit(&self, args: PoolArgs, audit_args: PoolAuditArgs) -> Result<(), Error> {
+        let Some(pool_url) = audit_args.pool_url.or(args.pool_url) else {
+            return Err(Error::Internal("Pool URL is required".to_string()));
+        };
+        let pool = Pool {
+            http_client: reqwest::Client::new(),
+            pool_url,
+        };
+        let authority = self.signer().pubkey();
+        let tolerance = amount_f64_to_u64(audit_args.tolerance) as i128;
+
+        // What the pool reports
+        let pool_address = pool.get_pool_address().await?.address;
+        let reported = pool.get_pool_member(self).await?.total_balance.max(0) as u64;
+
+        // What the chain says
+        let member_address = member_pda(authority, pool_address).0;
+        let onchain = get_member(&self.rpc_client, member_address).await?;
+
+        // What the pool announced while collecting
+        let label = pool.label();
+        let authority_str = authority.to_string();
+        let mut signatures = HashSet::new();
+        let records: Vec<_> = read_pool_events()
+            .map_err(|err| Error::Internal(format!("Failed to read pool events: {}", err)))?
+            .into_iter()
+            .filter(|record| record.pool == label && record.authority == authority_str)
+            .filter(|record| signatures.insert(record.signature.clone()))
+            .collect();
+        let recorded: u64 = records.iter().map(|record| record.member_reward).sum();
+        let recorded_since = records.first().map(|record| record.timestamp);
+
+        // Compare
+        let ledger_gap = recorded as i128 - reported as i128;
+        let commit_gap = reported as i128 - onchain.total_balance as i128;
+        let chain_gap = recorded as i128 - onchain.total_balance as i128;
+        let checks = vec![
+            AuditCheck::new(
+                "Recorded vs reported",
+                ledger_gap,
+                ledger_gap > tolerance,
+                if ledger_gap > tolerance {
+                    format!(
+                        "the pool reports {} BITZ less than the rewards it announced",
+                        amount_u64_to_string(ledger_gap as u64)
+                    )
+                } else {
+                    "the pool reports every recorded reward".to_string()
+                },
+            ),
+            AuditCheck::new(
+                "Reported vs onchain",
+                commit_gap,
+                commit_gap.abs() > tolerance,
+                if commit_gap > tolerance {
+                    format!(
+                        "{} BITZ not committed yet, run `bitz pool {} commit`",
+                        amount_u64_to_string(commit_gap as u64),
+                        pool.pool_url
+                    )
+                } else if commit_gap < -tolerance {
+                    format!(
+                        "the pool reports {} BITZ less than is attributed onchain",
+                        amount_u64_to_string(commit_gap.unsigned_abs() as u64)
+                    )
+                } else {
+                    "the reported balance is committed".to_string()
+                },
+            ),
+            AuditCheck::new(
+                "Recorded vs onchain",
+                chain_gap,
+                chain_gap > tolerance,
+                if chain_gap > tolerance {
+                    format!(
+                        "{} BITZ of recorded rewards are not attributed onchain",
+                        amount_u64_to_string(chain_gap as u64)
+                    )
+                } else {
+                    "every recorded reward is attributed onchain".to_string()
+                },
+            ),
+        ];
+        let flagged = checks.iter().any(|check| check.flagged);
+
+        if self.output == OutputFormat::Json {
+            print_json(&AuditOutput {
+                pool_url: pool.pool_url.clone(),
+                pool_address: pool_address.to_string(),
+                member_address: member_address.to_string(),
+                reported_total_balance: TokenAmount::bitz(reported),
+                onchain_total_balance: TokenAmount::bitz(onchain.total_balance),
+                onchain_balance: TokenAmount::bitz(onchain.balance),
+                recorded_rewards: TokenAmount::bitz(recorded),
+                recorded_rounds: records.len(),
+                recorded_since,
+                tolerance: TokenAmount::bitz(tolerance as u64),
+                checks,
+                flagged,
+            });
+            return Ok(());
+        }
+
+        // Balances
+        let mut data = vec![
+            TableData {
+                key: "Pool".to_string(),
+                value: pool_address.to_string(),
+            },
+            TableData {
+                key: "Member".to_string(),
+                value: member_address.to_string(),
+            },
+            TableData {
+                key: "Reported by pool".to_string(),
+                value: format!("{} BITZ", amount_u64_to_string(reported)),
+            },
+            TableData {
+                key: "Attributed onchain".to_string(),
+                value: format!("{} BITZ", amount_u64_to_string(onchain.total_balance)),
+            },
+            TableData {
+                key: "Claimable onchain".to_string(),
+                value: format!("{} BITZ", amount_u64_to_string(onchain.balance)),
+            },
+            TableData {
+                key: "Recorded rewards".to_string(),
+                value: format!(
+                    "{} BITZ over {} round(s){}",
+                    amount_u64_to_string(recorded),
+                    records.len(),
+                    recorded_since
+                        .map(|since| format!(" since {}", format_timestamp(since)))
+                        .unwrap_or_default()
+                ),
+            },
+        ];
+
+        // Checks
+        let checks_row = data.len();
+        data.extend(checks.iter().map(|check| TableData {
+            key: check.name.clone(),
+            value: check.status(),
+        }));
+
+        let mut table = Table::new(data);
+        table.with(Remove::row(Rows::first()));
+        table.with(Style::blank());
+        table.section_title(0, "Balances");
+        table.section_title(checks_row, "Checks");
+        println!("\n{table}\n");
+
+        if records.is_empty() {
+            println!(
+                "{} No events recorded for this pool yet. Rewards are recorded while collecting with --pool-url.\n",
+                "NOTE".bold().yellow()
+            );
+        } else if flagged {
+            println!(
+                "{} Gaps above {} BITZ were found. Keep the event log and the pool ledger of the same period when reporting them.\n",
+                "WARNING".bold().yellow(),
+                amount_u64_to_string(tolerance as u64)
+            );
+        }
+        Ok(())
+    }
+}
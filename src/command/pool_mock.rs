@@ -0,0 +1,164 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use colored::*;
+use ore_pool_api::state::pool_pda;
+use ore_pool_types::PoolAddress;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+};
+use tabled::{
+    settings::{object::Rows, Remove, Style},
+    Table,
+};
+
+use crate::{
+    args::PoolMockArgs,
+    error::Error,
+    utils::{amount_u64_to_string, TableData, TableSectionTitle},
+    Miner,
+};
+
+use super::pool_server::{self, ApiError, LandedRound, PoolOperator, PoolServer};
+
+/// Reward of a solution at the minimum difficulty. Each extra difficulty level
+/// doubles it, like the onchain reward rate.
+const MOCK_BASE_REWARD: u64 = 10u64.pow(eore_api::consts::TOKEN_DECIMALS as u32 - 4);
+
+/// Difficulty levels above the minimum that still increase the reward.
+const MOCK_MAX_REWARD_SHIFT: u64 = 16;
+
+/// Admits anyone and has no chain to commit balances to.
+struct MockOperator;
+
+#[async_trait]
+impl PoolOperator for MockOperator {
+    async fn admit(&self, _authority: Pubkey) -> Result<(), ApiError> {
+        Ok(())
+    }
+
+    async fn commit(&self, _authority: Pubkey, _total_balance: u64) -> Result<(), ApiError> {
+        Ok(())
+    }
+}
+
+impl Miner {
+    /// Serves the pool API from memory. Challenges are random and rotate on a
+    /// timer, contributions are checked with drillx and the best one of each
+    /// round "lands" with a synthetic reward, without touching the chain.
+    pub async fn pool_mock(&self, args: PoolMockArgs) -> Result<(), Error> {
+        // Advertise the given pool, or the pool of a throwaway authority
+        let address = match &args.address {
+            Some(address) => PoolAddress {
+                address: Pubkey::from_str(address)?,
+                bump: 0,
+            },
+            None => {
+                let (address, bump) = pool_pda(Keypair::new().pubkey());
+                PoolAddress { address, bump }
+            }
+        };
+        let server = Arc::new(PoolServer::new(
+            address,
+            args.round_time,
+            MockOperator,
+            None,
+        )?);
+        server.start_round(
+            rand::random(),
+            chrono::Utc::now().timestamp(),
+            args.min_difficulty,
+        );
+
+        // Rotate challenges. Clients collecting with `--pool-mock` derive the
+        // cutoff from the wall clock, others from the chain clock, which a
+        // local validator keeps close to it.
+        tokio::spawn({
+            let server = server.clone();
+            let min_difficulty = args.min_difficulty;
+            let round_time = args.round_time.max(1);
+            async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(round_time));
+                interval.tick().await;
+                let mut block = 0;
+                let mut last_hash_at = chrono::Utc::now().timestamp();
+                let mut balance = 0;
+                loop {
+                    interval.tick().await;
+                    let now = chrono::Utc::now().timestamp();
+                    let contributions = server.take_contributions();
+                    if let Some(best) = contributions.values().map(|(_, d)| *d).max() {
+                        block += 1;
+                        let shift = (best as u64).saturating_sub(min_difficulty);
+                        let net_reward = MOCK_BASE_REWARD << shift.min(MOCK_MAX_REWARD_SHIFT);
+                        balance += net_reward;
+                        server.land(
+                            &contributions,
+                            &LandedRound {
+                                signature: Signature::new_unique(),
+                                block,
+                                timestamp: now,
+                                timing: now - last_hash_at,
+                                balance,
+                                difficulty: best,
+                                net_reward,
+                                net_base_reward: net_reward,
+                                net_miner_boost_reward: 0,
+                            },
+                        );
+                        println!(
+                            "Round {} landed at difficulty {} for {} BITZ, {} contributor(s)",
+                            block,
+                            best,
+                            amount_u64_to_string(net_reward),
+                            contributions.len()
+                        );
+                    }
+                    server.start_round(rand::random(), now, min_difficulty);
+                    last_hash_at = now;
+                }
+            }
+        });
+
+        let listener = tokio::net::TcpListener::bind(("0.0.0.0", args.port))
+            .await
+            .map_err(|err| {
+                Error::Internal(format!("Failed to listen on port {}: {}", args.port, err))
+            })?;
+
+        // Print summary
+        let data = vec![
+            TableData {
+                key: "Address".to_string(),
+                value: server.address.to_string(),
+            },
+            TableData {
+                key: "Url".to_string(),
+                value: format!("http://127.0.0.1:{}", args.port),
+            },
+            TableData {
+                key: "Min difficulty".to_string(),
+                value: args.min_difficulty.to_string(),
+            },
+            TableData {
+                key: "Round time".to_string(),
+                value: format!("{}s", args.round_time),
+            },
+        ];
+        let mut table = Table::new(data);
+        table.with(Remove::row(Rows::first()));
+        table.with(Style::blank());
+        table.section_title(0, "Mock pool");
+        println!("\n{table}\n");
+        println!(
+            "{} Rewards are synthetic and nothing is submitted onchain. Collect with --pool-mock to skip the onchain member account and the chain clock.\n",
+            "NOTE".bold().yellow()
+        );
+
+        axum::serve(listener, pool_server::router(server))
+            .await
+            .map_err(|err| Error::Internal(format!("Mock pool stopped: {}", err)))
+    }
+}
@@ -0,0 +1,380 @@
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use axum::http::StatusCode;
+use colored::*;
+use drillx::Solution;
+use eore_api::state::proof_pda;
+use ore_pool_api::state::{member_pda, pool_pda};
+use ore_pool_types::PoolAddress;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    keccak, system_program, sysvar,
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signature, signer::Signer};
+use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
+use tabled::{
+    settings::{object::Rows, Remove, Style},
+    Table,
+};
+
+use crate::{
+    args::PoolServeArgs,
+    error::Error,
+    utils::{
+        amount_u64_to_string, get_config, get_member, get_pool, get_updated_proof_with_authority,
+        mine_event_from_logs, ComputeBudget, TableData, TableSectionTitle,
+    },
+    Miner,
+};
+
+use super::pool_server::{self, ApiError, LandedRound, PoolOperator, PoolServer};
+
+/// Seconds members have to collect on a challenge of the pool proof.
+const ROUND_TIME: u64 = 60;
+
+/// Attribute instructions per transaction.
+const ATTRIBUTE_BATCH_SIZE: usize = 8;
+
+/// Compute units of one attribute instruction.
+const ATTRIBUTE_COMPUTE_UNITS: u32 = 20_000;
+
+/// Seconds to wait for a landed transaction to be queryable.
+const EVENT_FETCH_TIMEOUT: u64 = 30;
+
+/// Seconds to wait after a failed round before retrying.
+const ROUND_RETRY_DELAY: u64 = 5;
+
+/// Runs the pool as its authority: members must join onchain before they
+/// register, and balances are attributed with the authority's signature.
+struct ServeOperator {
+    miner: Miner,
+    pool_address: Pubkey,
+}
+
+impl ServeOperator {
+    /// Sets the onchain balances of the given members to their totals.
+    async fn attribute(&self, members: &[(Pubkey, u64)]) -> Result<Signature, Error> {
+        let authority = self.miner.signer().pubkey();
+        let ixs: Vec<_> = members
+            .iter()
+            .map(|(member, total_balance)| {
+                ore_pool_api::sdk::attribute(authority, *member, *total_balance)
+            })
+            .collect();
+        self.miner
+            .send_and_confirm(
+                &ixs,
+                ComputeBudget::Fixed(ATTRIBUTE_COMPUTE_UNITS * ixs.len() as u32),
+                false,
+            )
+            .await
+    }
+}
+
+#[async_trait]
+impl PoolOperator for ServeOperator {
+    async fn admit(&self, authority: Pubkey) -> Result<(), ApiError> {
+        let member_address = member_pda(authority, self.pool_address).0;
+        match get_member(&self.miner.rpc_client, member_address).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err((
+                StatusCode::FORBIDDEN,
+                format!("{} has not joined the pool onchain", authority),
+            )),
+        }
+    }
+
+    async fn commit(&self, authority: Pubkey, total_balance: u64) -> Result<(), ApiError> {
+        self.attribute(&[(authority, total_balance)])
+            .await
+            .map(|_| ())
+            .map_err(|err| (StatusCode::BAD_GATEWAY, err.to_string()))
+    }
+}
+
+impl Miner {
+    /// Operates the pool of the signer. Members collect on the pool proof in
+    /// their own share of the nonce space, the best contribution of each round
+    /// is submitted onchain and its reward is split among the contributors.
+    pub async fn pool_serve(&self, args: PoolServeArgs) -> Result<(), Error> {
+        let authority = self.signer().pubkey();
+        let (pool_address, bump) = pool_pda(authority);
+        if get_pool(&self.rpc_client, pool_address).await.is_err() {
+            return Err(Error::Internal(format!(
+                "No pool found for authority {}. Launch the pool onchain first.",
+                authority
+            )));
+        }
+        let server = Arc::new(PoolServer::new(
+            PoolAddress {
+                address: pool_address,
+                bump,
+            },
+            ROUND_TIME,
+            ServeOperator {
+                miner: self.clone(),
+                pool_address,
+            },
+            Some(PathBuf::from(&args.ledger)),
+        )?);
+
+        let listener = tokio::net::TcpListener::bind(("0.0.0.0", args.port))
+            .await
+            .map_err(|err| {
+                Error::Internal(format!("Failed to listen on port {}: {}", args.port, err))
+            })?;
+
+        // Print summary
+        let data = vec![
+            TableData {
+                key: "Authority".to_string(),
+                value: authority.to_string(),
+            },
+            TableData {
+                key: "Address".to_string(),
+                value: pool_address.to_string(),
+            },
+            TableData {
+                key: "Port".to_string(),
+                value: args.port.to_string(),
+            },
+            TableData {
+                key: "Members".to_string(),
+                value: server.members().len().to_string(),
+            },
+            TableData {
+                key: "Ledger".to_string(),
+                value: args.ledger.clone(),
+            },
+        ];
+        let mut table = Table::new(data);
+        table.with(Remove::row(Rows::first()));
+        table.with(Style::blank());
+        table.section_title(0, "Pool");
+        println!("\n{table}\n");
+
+        // Serve the API
+        tokio::spawn({
+            let server = server.clone();
+            async move {
+                if let Err(err) = axum::serve(listener, pool_server::router(server)).await {
+                    println!("{} Pool server stopped: {}", "ERROR".bold().red(), err);
+                    std::process::exit(1);
+                }
+            }
+        });
+
+        // Run rounds, attributing balances onchain at intervals
+        let mut last_hash_at = 0;
+        let mut last_attribution = Instant::now();
+        loop {
+            match self.pool_serve_round(&server, &args, last_hash_at).await {
+                Ok(landed_at) => last_hash_at = landed_at,
+                Err(err) => {
+                    println!("{} {}", "ERROR".bold().red(), err);
+                    tokio::time::sleep(Duration::from_secs(ROUND_RETRY_DELAY)).await;
+                }
+            }
+            if last_attribution.elapsed() >= Duration::from_secs(args.attribute_interval) {
+                self.pool_attribute(&server).await;
+                last_attribution = Instant::now();
+            }
+        }
+    }
+
+    /// Opens a round on the next challenge of the pool proof, waits for the
+    /// cutoff and submits the best contribution. Returns the `last_hash_at` of
+    /// the challenge that landed.
+    async fn pool_serve_round(
+        &self,
+        server: &PoolServer<ServeOperator>,
+        args: &PoolServeArgs,
+        last_hash_at: i64,
+    ) -> Result<i64, Error> {
+        // Open the round
+        let proof =
+            get_updated_proof_with_authority(&self.rpc_client, server.address, last_hash_at, None)
+                .await?;
+        let config = get_config(&self.rpc_client).await?;
+        server.start_round(proof.challenge, proof.last_hash_at, config.min_difficulty);
+        let cutoff_time = self
+            .get_cutoff(proof.last_hash_at, args.buffer_time)
+            .await?;
+        tokio::time::sleep(Duration::from_secs(cutoff_time)).await;
+
+        // Wait for at least one contribution
+        let contributions = loop {
+            let contributions = server.take_contributions();
+            if !contributions.is_empty() {
+                break contributions;
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        };
+        let (solution, difficulty) = contributions
+            .values()
+            .max_by_key(|(_, difficulty)| *difficulty)
+            .copied()
+            .unwrap();
+
+        // Attest to the contributions the solution was picked from
+        let mut attested: Vec<_> = contributions
+            .iter()
+            .map(|(authority, (_, difficulty))| (*authority, *difficulty))
+            .collect();
+        attested.sort();
+        let attestation: Vec<[u8; 36]> = attested
+            .iter()
+            .map(|(authority, difficulty)| {
+                let mut entry = [0; 36];
+                entry[..32].copy_from_slice(authority.as_ref());
+                entry[32..].copy_from_slice(&difficulty.to_le_bytes());
+                entry
+            })
+            .collect();
+        let attestation = keccak::hashv(
+            &attestation
+                .iter()
+                .map(|entry| entry.as_slice())
+                .collect::<Vec<_>>(),
+        )
+        .to_bytes();
+
+        // Submit the best solution
+        let signer = self.signer();
+        let mut ixs = vec![eore_api::sdk::auth(proof_pda(server.address).0)];
+        if self.should_reset(config).await? {
+            ixs.push(eore_api::sdk::reset(signer.pubkey()));
+        }
+        ixs.push(submit(
+            signer.pubkey(),
+            server.address,
+            solution,
+            attestation,
+            self.find_bus().await,
+        ));
+        let sig = self
+            .send_and_confirm(&ixs, ComputeBudget::Dynamic, false)
+            .await?;
+
+        // Split the reward
+        match self.fetch_landed_round(sig).await {
+            Some(round) => {
+                server.land(&contributions, &round);
+                println!(
+                    "Round {} landed at difficulty {} for {} BITZ, {} contributor(s)",
+                    sig,
+                    round.difficulty,
+                    amount_u64_to_string(round.net_reward),
+                    contributions.len()
+                );
+            }
+            None => println!(
+                "{} Round {} landed at difficulty {} but its reward could not be read. Contributors were not credited.",
+                "WARNING".bold().yellow(),
+                sig,
+                difficulty
+            ),
+        }
+        Ok(proof.last_hash_at)
+    }
+
+    /// Reads the mine event of a landed pool transaction.
+    async fn fetch_landed_round(&self, sig: Signature) -> Option<LandedRound> {
+        let deadline = Instant::now() + Duration::from_secs(EVENT_FETCH_TIMEOUT);
+        let tx = loop {
+            match self
+                .rpc_client
+                .get_transaction(&sig, UiTransactionEncoding::Json)
+                .await
+            {
+                Ok(tx) => break tx,
+                Err(_) if Instant::now() < deadline => {
+                    tokio::time::sleep(Duration::from_secs(1)).await
+                }
+                Err(_) => return None,
+            }
+        };
+        let meta = tx.transaction.meta?;
+        let OptionSerializer::Some(log_messages) = &meta.log_messages else {
+            return None;
+        };
+        let event = mine_event_from_logs(log_messages)?;
+        Some(LandedRound {
+            signature: sig,
+            block: tx.slot,
+            timestamp: tx.block_time.unwrap_or_default(),
+            timing: event.timing,
+            balance: event.balance,
+            difficulty: event.difficulty as u32,
+            net_reward: event.net_reward,
+            net_base_reward: event.net_base_reward,
+            net_miner_boost_reward: event.net_miner_boost_reward,
+        })
+    }
+
+    /// Attributes every member whose balance grew since it was last
+    /// attributed.
+    async fn pool_attribute(&self, server: &PoolServer<ServeOperator>) {
+        let pending: Vec<(Pubkey, u64)> = server
+            .members()
+            .iter()
+            .filter(|member| member.attributed < member.total_balance)
+            .map(|member| (member.authority(), member.total_balance))
+            .collect();
+        for batch in pending.chunks(ATTRIBUTE_BATCH_SIZE) {
+            match server.operator.attribute(batch).await {
+                Ok(sig) => {
+                    for (authority, total_balance) in batch {
+                        server.set_attributed(authority, *total_balance);
+                    }
+                    println!("Attributed {} member balance(s) in {}", batch.len(), sig);
+                }
+                Err(err) => println!(
+                    "{} Failed to attribute member balances: {}",
+                    "WARNING".bold().yellow(),
+                    err
+                ),
+            }
+        }
+    }
+}
+
+/// Builds the pool submit instruction against the eore program. The pool
+/// program's own `sdk::submit` points at the ORE program, its proof and boost
+/// accounts, which do not exist on this chain.
+fn submit(
+    signer: Pubkey,
+    pool: Pubkey,
+    solution: Solution,
+    attestation: [u8; 32],
+    bus: Pubkey,
+) -> Instruction {
+    let (boost_config, _) = eore_boost_api::state::config_pda();
+    Instruction {
+        program_id: ore_pool_api::ID,
+        accounts: vec![
+            AccountMeta::new(signer, true),
+            AccountMeta::new(bus, false),
+            AccountMeta::new_readonly(eore_api::consts::CONFIG_ADDRESS, false),
+            AccountMeta::new(pool, false),
+            AccountMeta::new(proof_pda(pool).0, false),
+            AccountMeta::new_readonly(eore_api::ID, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(sysvar::instructions::ID, false),
+            AccountMeta::new_readonly(sysvar::slot_hashes::ID, false),
+            AccountMeta::new_readonly(boost_config, false),
+            AccountMeta::new(proof_pda(boost_config).0, false),
+        ],
+        data: ore_pool_api::instruction::Submit {
+            attestation,
+            digest: solution.d,
+            nonce: solution.n,
+        }
+        .to_bytes(),
+    }
+}
@@ -0,0 +1,577 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+    routing::{get, post},
+    Json, Router,
+};
+use drillx::Solution;
+use futures::Stream;
+use ore_pool_api::state::member_pda;
+use ore_pool_types::{
+    Challenge, ContributePayload, Member, MemberChallenge, PoolAddress, PoolMemberMiningEvent,
+    RegisterPayload, UpdateBalancePayload,
+};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use tokio::sync::broadcast;
+
+use crate::error::Error;
+
+use super::pool::PoolCapabilities;
+
+/// Devices each member may collect with, as announced in `MemberChallenge`.
+const NUM_DEVICES: u8 = 5;
+
+/// Shortest time between two commits requested by the same member, as each
+/// one costs the pool a transaction.
+const COMMIT_INTERVAL: Duration = Duration::from_secs(300);
+
+pub type ApiError = (StatusCode, String);
+
+/// What sets a pool apart from the shared HTTP API: who may join and how
+/// balances reach the chain.
+#[async_trait]
+pub trait PoolOperator: Send + Sync + 'static {
+    /// Checks that a member may join before it is registered.
+    async fn admit(&self, authority: Pubkey) -> Result<(), ApiError>;
+
+    /// Commits the balance of a member onchain on its request.
+    async fn commit(&self, authority: Pubkey, total_balance: u64) -> Result<(), ApiError>;
+}
+
+/// A member as kept in the ledger file.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LedgerMember {
+    pub id: u64,
+    pub authority: String,
+    /// Rewards attributed to the member over its lifetime.
+    pub total_balance: u64,
+    /// Part of `total_balance` already attributed onchain.
+    pub attributed: u64,
+}
+
+impl LedgerMember {
+    pub fn authority(&self) -> Pubkey {
+        Pubkey::from_str(&self.authority).unwrap_or_default()
+    }
+
+    fn to_member(&self, pool_address: Pubkey) -> Member {
+        Member {
+            id: self.id as i64,
+            address: member_pda(self.authority(), pool_address).0.to_string(),
+            authority: self.authority.clone(),
+            pool_address: pool_address.to_string(),
+            total_balance: self.total_balance as i64,
+            is_approved: true,
+            is_kyc: false,
+            is_synced: self.attributed == self.total_balance,
+        }
+    }
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct Ledger {
+    members: Vec<LedgerMember>,
+}
+
+/// A round that landed onchain, or pretended to.
+pub struct LandedRound {
+    pub signature: Signature,
+    pub block: u64,
+    pub timestamp: i64,
+    pub timing: i64,
+    /// Balance of the pool proof after the round.
+    pub balance: u64,
+    pub difficulty: u32,
+    pub net_reward: u64,
+    pub net_base_reward: u64,
+    pub net_miner_boost_reward: u64,
+}
+
+/// A landed round as seen by one member.
+#[derive(Clone, Copy)]
+struct MemberRound {
+    signature: Signature,
+    block: u64,
+    timestamp: i64,
+    timing: i64,
+    balance: u64,
+    difficulty: u32,
+    net_reward: u64,
+    net_base_reward: u64,
+    net_miner_boost_reward: u64,
+    member_difficulty: u32,
+    member_reward: u64,
+    last_hash_at: i64,
+}
+
+impl MemberRound {
+    fn event(&self) -> PoolMemberMiningEvent {
+        PoolMemberMiningEvent {
+            signature: self.signature,
+            block: self.block,
+            timestamp: self.timestamp as _,
+            timing: self.timing,
+            balance: self.balance,
+            difficulty: self.difficulty as _,
+            net_reward: self.net_reward,
+            net_base_reward: self.net_base_reward,
+            net_miner_boost_reward: self.net_miner_boost_reward,
+            net_staker_boost_reward: 0,
+            member_difficulty: self.member_difficulty as _,
+            member_reward: self.member_reward,
+            last_hash_at: self.last_hash_at as _,
+        }
+    }
+}
+
+struct ServerState {
+    /// Members in registration order, so a member's index is its id.
+    members: Vec<LedgerMember>,
+    challenge: [u8; 32],
+    lash_hash_at: i64,
+    min_difficulty: u64,
+    /// Best contribution of each member to the current challenge.
+    contributions: HashMap<Pubkey, (Solution, u32)>,
+    /// Latest landed round of each member.
+    landed: HashMap<Pubkey, MemberRound>,
+    /// When each member last requested a commit.
+    last_commits: HashMap<Pubkey, Instant>,
+}
+
+/// State behind the HTTP API of a pool. Members collect on the current
+/// challenge, each in its own share of the nonce space, and rewards of landed
+/// rounds are split among the contributors in proportion to `2^difficulty`.
+pub struct PoolServer<O> {
+    pub address: Pubkey,
+    bump: u8,
+    /// Seconds members have to collect on a challenge.
+    round_time: u64,
+    pub operator: O,
+    /// Where member balances are persisted, if anywhere.
+    ledger_path: Option<PathBuf>,
+    state: Mutex<ServerState>,
+    /// Notifies push subscribers of a new challenge.
+    challenges: broadcast::Sender<()>,
+    /// Notifies push subscribers of a landed round.
+    events: broadcast::Sender<()>,
+}
+
+impl<O: PoolOperator> PoolServer<O> {
+    /// Creates the server, loading members from the ledger file if it exists.
+    pub fn new(
+        address: PoolAddress,
+        round_time: u64,
+        operator: O,
+        ledger_path: Option<PathBuf>,
+    ) -> Result<Self, Error> {
+        let ledger = match &ledger_path {
+            Some(path) if path.exists() => {
+                let contents = std::fs::read_to_string(path).map_err(|err| {
+                    Error::Internal(format!("Failed to read {}: {}", path.display(), err))
+                })?;
+                serde_json::from_str(&contents).map_err(|err| {
+                    Error::Internal(format!("Failed to parse {}: {}", path.display(), err))
+                })?
+            }
+            _ => Ledger::default(),
+        };
+        Ok(Self {
+            address: address.address,
+            bump: address.bump,
+            round_time,
+            operator,
+            ledger_path,
+            state: Mutex::new(ServerState {
+                members: ledger.members,
+                challenge: [0; 32],
+                lash_hash_at: 0,
+                min_difficulty: 0,
+                contributions: HashMap::new(),
+                landed: HashMap::new(),
+                last_commits: HashMap::new(),
+            }),
+            challenges: broadcast::channel(16).0,
+            events: broadcast::channel(16).0,
+        })
+    }
+
+    pub fn members(&self) -> Vec<LedgerMember> {
+        self.state.lock().unwrap().members.clone()
+    }
+
+    /// Opens a new round. Contributions to the previous challenge are dropped.
+    pub fn start_round(&self, challenge: [u8; 32], lash_hash_at: i64, min_difficulty: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.challenge = challenge;
+        state.lash_hash_at = lash_hash_at;
+        state.min_difficulty = min_difficulty;
+        state.contributions.clear();
+        drop(state);
+        let _ = self.challenges.send(());
+    }
+
+    /// Takes the contributions collected so far. Later contributions to the
+    /// same challenge start a new set.
+    pub fn take_contributions(&self) -> HashMap<Pubkey, (Solution, u32)> {
+        std::mem::take(&mut self.state.lock().unwrap().contributions)
+    }
+
+    /// Splits the reward of a landed round among its contributors and pushes
+    /// their mining events.
+    pub fn land(&self, contributions: &HashMap<Pubkey, (Solution, u32)>, round: &LandedRound) {
+        let mut state = self.state.lock().unwrap();
+        let total_weight: u128 = contributions.values().map(|(_, d)| 1u128 << d).sum();
+        for (authority, (_, difficulty)) in contributions {
+            let member_reward = (round.net_reward as u128 * (1u128 << difficulty))
+                .checked_div(total_weight)
+                .unwrap_or(0) as u64;
+            if let Some(member) = find_member(&state.members, authority) {
+                state.members[member].total_balance += member_reward;
+            }
+            let member_round = MemberRound {
+                signature: round.signature,
+                block: round.block,
+                timestamp: round.timestamp,
+                timing: round.timing,
+                balance: round.balance,
+                difficulty: round.difficulty,
+                net_reward: round.net_reward,
+                net_base_reward: round.net_base_reward,
+                net_miner_boost_reward: round.net_miner_boost_reward,
+                member_difficulty: *difficulty,
+                member_reward,
+                last_hash_at: state.lash_hash_at,
+            };
+            state.landed.insert(*authority, member_round);
+        }
+        self.save(&state);
+        drop(state);
+        let _ = self.events.send(());
+    }
+
+    /// Records that a member's balance was attributed onchain.
+    pub fn set_attributed(&self, authority: &Pubkey, total_balance: u64) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(member) = find_member(&state.members, authority) {
+            let member = &mut state.members[member];
+            member.attributed = member.attributed.max(total_balance);
+        }
+        self.save(&state);
+    }
+
+    fn member(&self, authority: &Pubkey) -> Option<LedgerMember> {
+        let state = self.state.lock().unwrap();
+        find_member(&state.members, authority).map(|member| state.members[member].clone())
+    }
+
+    // Clients pick their own device ids, the field is only kept for old ones
+    #[allow(deprecated)]
+    fn member_challenge(&self, authority: &Pubkey) -> Option<MemberChallenge> {
+        let state = self.state.lock().unwrap();
+        find_member(&state.members, authority)?;
+        Some(MemberChallenge {
+            challenge: Challenge {
+                challenge: state.challenge,
+                lash_hash_at: state.lash_hash_at,
+                min_difficulty: state.min_difficulty,
+                cutoff_time: self.round_time,
+            },
+            num_total_members: state.members.len() as u64,
+            device_id: 0,
+            num_devices: NUM_DEVICES,
+            unix_timestamp: chrono::Utc::now().timestamp(),
+        })
+    }
+
+    fn latest_event(&self, authority: &Pubkey) -> Option<PoolMemberMiningEvent> {
+        let state = self.state.lock().unwrap();
+        state.landed.get(authority).map(MemberRound::event)
+    }
+
+    fn save(&self, state: &ServerState) {
+        let Some(path) = &self.ledger_path else {
+            return;
+        };
+        let ledger = Ledger {
+            members: state.members.clone(),
+        };
+        let result = serde_json::to_string_pretty(&ledger)
+            .map_err(|err| err.to_string())
+            .and_then(|json| {
+                // Write then rename, so a crash never leaves a truncated ledger
+                let tmp = path.with_extension("tmp");
+                std::fs::write(&tmp, json)
+                    .and_then(|_| std::fs::rename(&tmp, path))
+                    .map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            eprintln!("error: Failed to save {}: {}", path.display(), err);
+        }
+    }
+}
+
+/// Routes of the pool API as consumed by `Pool`, plus the push streams
+/// advertised at `/capabilities`.
+pub fn router<O: PoolOperator>(server: Arc<PoolServer<O>>) -> Router {
+    Router::new()
+        .route("/address", get(address::<O>))
+        .route("/pool-address", get(address::<O>))
+        .route("/capabilities", get(capabilities))
+        .route("/register", post(register::<O>))
+        .route("/member/:authority", get(member::<O>))
+        .route("/challenge/:authority", get(challenge::<O>))
+        .route("/challenge/:authority/stream", get(challenge_stream::<O>))
+        .route("/contribute", post(contribute::<O>))
+        .route("/event/latest/:authority", get(latest_event::<O>))
+        .route("/event/:authority/stream", get(event_stream::<O>))
+        .route("/commit", post(commit::<O>))
+        .route("/update-balance", post(commit::<O>))
+        .with_state(server)
+}
+
+async fn address<O: PoolOperator>(State(server): State<Arc<PoolServer<O>>>) -> Json<PoolAddress> {
+    Json(PoolAddress {
+        address: server.address,
+        bump: server.bump,
+    })
+}
+
+async fn capabilities() -> Json<PoolCapabilities> {
+    Json(PoolCapabilities {
+        challenge_stream: true,
+        event_stream: true,
+    })
+}
+
+async fn register<O: PoolOperator>(
+    State(server): State<Arc<PoolServer<O>>>,
+    Json(payload): Json<RegisterPayload>,
+) -> Result<Json<Member>, ApiError> {
+    if let Some(member) = server.member(&payload.authority) {
+        return Ok(Json(member.to_member(server.address)));
+    }
+    server.operator.admit(payload.authority).await?;
+    let mut state = server.state.lock().unwrap();
+    let member = match find_member(&state.members, &payload.authority) {
+        Some(member) => state.members[member].clone(),
+        None => {
+            let member = LedgerMember {
+                id: state.members.len() as u64,
+                authority: payload.authority.to_string(),
+                total_balance: 0,
+                attributed: 0,
+            };
+            state.members.push(member.clone());
+            server.save(&state);
+            println!(
+                "Registered member {} as id {}",
+                payload.authority, member.id
+            );
+            member
+        }
+    };
+    Ok(Json(member.to_member(server.address)))
+}
+
+async fn member<O: PoolOperator>(
+    State(server): State<Arc<PoolServer<O>>>,
+    Path(authority): Path<String>,
+) -> Result<Json<Member>, ApiError> {
+    let authority = parse_authority(&authority)?;
+    let member = server.member(&authority).ok_or_else(not_registered)?;
+    Ok(Json(member.to_member(server.address)))
+}
+
+async fn challenge<O: PoolOperator>(
+    State(server): State<Arc<PoolServer<O>>>,
+    Path(authority): Path<String>,
+) -> Result<Json<MemberChallenge>, ApiError> {
+    let authority = parse_authority(&authority)?;
+    server
+        .member_challenge(&authority)
+        .map(Json)
+        .ok_or_else(not_registered)
+}
+
+async fn challenge_stream<O: PoolOperator>(
+    State(server): State<Arc<PoolServer<O>>>,
+    Path(authority): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, ApiError> {
+    let authority = parse_authority(&authority)?;
+    server.member(&authority).ok_or_else(not_registered)?;
+    let updates = server.challenges.subscribe();
+    Ok(push_updates(server, updates, move |server| {
+        server.member_challenge(&authority)
+    }))
+}
+
+async fn contribute<O: PoolOperator>(
+    State(server): State<Arc<PoolServer<O>>>,
+    Json(payload): Json<ContributePayload>,
+) -> Result<StatusCode, ApiError> {
+    let mut state = server.state.lock().unwrap();
+    find_member(&state.members, &payload.authority).ok_or_else(not_registered)?;
+    if !payload
+        .signature
+        .verify(&payload.authority.to_bytes(), &payload.solution.to_bytes())
+    {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid signature".to_string()));
+    }
+    if !payload.solution.is_valid(&state.challenge) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Solution does not match the current challenge".to_string(),
+        ));
+    }
+    let difficulty = payload.solution.to_hash().difficulty();
+    if (difficulty as u64) < state.min_difficulty {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Difficulty {} is below the minimum of {}",
+                difficulty, state.min_difficulty
+            ),
+        ));
+    }
+    let best = state
+        .contributions
+        .entry(payload.authority)
+        .or_insert((payload.solution, difficulty));
+    if difficulty > best.1 {
+        *best = (payload.solution, difficulty);
+    }
+    Ok(StatusCode::OK)
+}
+
+async fn latest_event<O: PoolOperator>(
+    State(server): State<Arc<PoolServer<O>>>,
+    Path(authority): Path<String>,
+) -> Result<Json<PoolMemberMiningEvent>, ApiError> {
+    let authority = parse_authority(&authority)?;
+    server
+        .latest_event(&authority)
+        .map(Json)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "No event yet".to_string()))
+}
+
+async fn event_stream<O: PoolOperator>(
+    State(server): State<Arc<PoolServer<O>>>,
+    Path(authority): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, ApiError> {
+    let authority = parse_authority(&authority)?;
+    server.member(&authority).ok_or_else(not_registered)?;
+    let updates = server.events.subscribe();
+    Ok(push_updates(server, updates, move |server| {
+        server.latest_event(&authority)
+    }))
+}
+
+/// Commits the member's balance as the pool sees it. The transaction in the
+/// payload is not co-signed, so members can't attribute more than they earned.
+async fn commit<O: PoolOperator>(
+    State(server): State<Arc<PoolServer<O>>>,
+    Json(payload): Json<UpdateBalancePayload>,
+) -> Result<StatusCode, ApiError> {
+    let member = server
+        .member(&payload.authority)
+        .ok_or_else(not_registered)?;
+    let tx = &payload.transaction;
+    let signed = tx.message.account_keys.first() == Some(&payload.authority)
+        && tx.signatures.first().is_some_and(|signature| {
+            signature.verify(&payload.authority.to_bytes(), &tx.message_data())
+        });
+    if !signed {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid signature".to_string()));
+    }
+    {
+        let mut state = server.state.lock().unwrap();
+        let now = Instant::now();
+        if let Some(last_commit) = state.last_commits.get(&payload.authority) {
+            if now.duration_since(*last_commit) < COMMIT_INTERVAL {
+                return Err((
+                    StatusCode::TOO_MANY_REQUESTS,
+                    format!(
+                        "Commits are limited to one every {} seconds",
+                        COMMIT_INTERVAL.as_secs()
+                    ),
+                ));
+            }
+        }
+        state.last_commits.insert(payload.authority, now);
+    }
+    if member.attributed < member.total_balance {
+        server
+            .operator
+            .commit(payload.authority, member.total_balance)
+            .await?;
+        server.set_attributed(&payload.authority, member.total_balance);
+    }
+    Ok(StatusCode::OK)
+}
+
+/// Pushes a value on connect and after each update, skipping updates without
+/// one.
+fn push_updates<O, T, F>(
+    server: Arc<PoolServer<O>>,
+    updates: broadcast::Receiver<()>,
+    value: F,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>>
+where
+    O: PoolOperator,
+    T: Serialize,
+    F: Fn(&PoolServer<O>) -> Option<T> + Send + 'static,
+{
+    let stream = futures::stream::unfold(
+        (server, updates, value, false),
+        |(server, mut updates, value, mut wait)| async move {
+            loop {
+                if wait {
+                    match updates.recv().await {
+                        Ok(()) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+                wait = true;
+                if let Some(data) = value(&server) {
+                    let event = Event::default().json_data(data);
+                    return Some((event, (server, updates, value, wait)));
+                }
+            }
+        },
+    );
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+fn find_member(members: &[LedgerMember], authority: &Pubkey) -> Option<usize> {
+    let authority = authority.to_string();
+    members
+        .iter()
+        .position(|member| member.authority == authority)
+}
+
+fn parse_authority(authority: &str) -> Result<Pubkey, ApiError> {
+    Pubkey::from_str(authority).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Invalid authority {}", authority),
+        )
+    })
+}
+
+fn not_registered() -> ApiError {
+    (
+        StatusCode::NOT_FOUND,
+        "Member is not registered".to_string(),
+    )
+}
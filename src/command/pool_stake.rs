@@ -0,0 +1,228 @@
+use std::str::FromStr;
+
+use serde::Serialize;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use spl_token::{amount_to_ui_amount, try_ui_amount_into_amount};
+use tabled::{
+    settings::{
+        object::{Columns, Rows},
+        style::BorderColor,
+        Alignment, Border, Color, Highlight, Style,
+    },
+    Table, Tabled,
+};
+
+use crate::{
+    args::{PoolArgs, PoolSharesArgs, UnstakeArgs},
+    error::Error,
+    utils::{
+        get_mint, get_pool_shares, get_shares, print_json, ComputeBudget, OutputFormat, TokenAmount,
+    },
+    Miner,
+};
+
+use super::pool::Pool;
+
+/// JSON output of `pool shares`, one per pool and mint. Shares are fractions
+/// between 0 and 1, `null` when the pool has no deposits.
+#[derive(Serialize)]
+struct PoolShareOutput {
+    address: String,
+    pool: String,
+    mint: String,
+    symbol: String,
+    balance: TokenAmount,
+    pool_deposits: TokenAmount,
+    share: Option<f64>,
+}
+
+impl Miner {
+    /// Withdraws boost tokens from the signer's pool share to a token account.
+    /// Pool stake lives in the legacy boost program, new stake goes to the
+    /// global boost with `stake deposit`.
+    pub async fn pool_unstake(
+        &self,
+        args: PoolArgs,
+        unstake_args: UnstakeArgs,
+    ) -> Result<(), Error> {
+        let pool = pool_from_url(unstake_args.pool_url.or(args.pool_url))?;
+        let pool_address = pool.get_pool_address().await?.address;
+        let mint_address = Pubkey::from_str(&unstake_args.mint)?;
+        let signer = self.signer();
+
+        // Get share account
+        let Ok(share) = pool
+            .get_staker_onchain(self, pool_address, mint_address)
+            .await
+        else {
+            return Err(Error::Internal(format!(
+                "No shares of mint {} in pool {}",
+                mint_address, pool_address
+            )));
+        };
+
+        // Get recipient token account, creating it if necessary
+        let mut ixs = vec![];
+        let recipient = match &unstake_args.token_account {
+            Some(address) => Pubkey::from_str(address)?,
+            None => spl_associated_token_account::get_associated_token_address(
+                &signer.pubkey(),
+                &mint_address,
+            ),
+        };
+        if !matches!(
+            self.rpc_client.get_token_account(&recipient).await,
+            Ok(Some(_))
+        ) {
+            if unstake_args.token_account.is_some() {
+                return Err(Error::Internal(format!(
+                    "Token account {} not found",
+                    recipient
+                )));
+            }
+            ixs.push(
+                spl_associated_token_account::instruction::create_associated_token_account(
+                    &signer.pubkey(),
+                    &signer.pubkey(),
+                    &mint_address,
+                    &spl_token::id(),
+                ),
+            );
+        }
+
+        // Parse amount
+        let mint = get_mint(&self.rpc_client, mint_address).await?;
+        let amount = match unstake_args.amount {
+            // Parse the shortest decimal form, multiplying the float would truncate
+            Some(amount) => try_ui_amount_into_amount(amount.to_string(), mint.decimals)?,
+            None => share.balance,
+        };
+        if amount == 0 || amount > share.balance {
+            return Err(Error::Internal(format!(
+                "Cannot unstake {}, the share balance is {}",
+                amount_to_ui_amount(amount, mint.decimals),
+                amount_to_ui_amount(share.balance, mint.decimals)
+            )));
+        }
+
+        // Send tx
+        ixs.push(ore_pool_api::sdk::unstake(
+            signer.pubkey(),
+            mint_address,
+            pool_address,
+            recipient,
+            amount,
+        ));
+        self.send_and_confirm(&ixs, ComputeBudget::Fixed(100_000), false)
+            .await?;
+        println!(
+            "Unstaked {} from pool {}",
+            amount_to_ui_amount(amount, mint.decimals),
+            pool_address
+        );
+        Ok(())
+    }
+
+    /// Lists the pool shares of an authority with their part of all the shares
+    /// of each pool and mint.
+    pub async fn pool_shares(&self, shares_args: PoolSharesArgs) -> Result<(), Error> {
+        let authority = match &shares_args.authority {
+            Some(authority) => Pubkey::from_str(authority)?,
+            None => self.signer().pubkey(),
+        };
+        let mut shares = get_shares(&self.rpc_client, authority).await?;
+        shares.sort_by_key(|(_, share)| (share.pool.to_string(), share.mint.to_string()));
+
+        // Aggregate data
+        let mut data = vec![];
+        let mut output = vec![];
+        for (address, share) in shares {
+            let mint = get_mint(&self.rpc_client, share.mint).await?;
+            let symbol = self.get_mint_symbol(share.mint).await;
+            let pool_deposits: u64 = get_pool_shares(&self.rpc_client, share.pool, share.mint)
+                .await?
+                .iter()
+                .map(|(_, share)| share.balance)
+                .sum();
+            let fraction = (pool_deposits > 0).then(|| share.balance as f64 / pool_deposits as f64);
+            output.push(PoolShareOutput {
+                address: address.to_string(),
+                pool: share.pool.to_string(),
+                mint: share.mint.to_string(),
+                symbol: symbol.clone(),
+                balance: TokenAmount::new(share.balance, mint.decimals),
+                pool_deposits: TokenAmount::new(pool_deposits, mint.decimals),
+                share: fraction,
+            });
+            data.push(PoolShareTableData {
+                pool: share.pool.to_string(),
+                mint: share.mint.to_string(),
+                symbol,
+                pool_deposits: amount_to_ui_amount(pool_deposits, mint.decimals).to_string(),
+                my_shares: amount_to_ui_amount(share.balance, mint.decimals).to_string(),
+                my_share: match fraction {
+                    Some(fraction) => format!("{:.8}%", fraction * 100f64),
+                    None => "NaN".to_string(),
+                },
+            });
+        }
+        if self.output == OutputFormat::Json {
+            print_json(&output);
+            return Ok(());
+        }
+        if data.is_empty() {
+            println!("No pool shares found for {}", authority);
+            return Ok(());
+        }
+
+        // Build table
+        let mut table = Table::new(data);
+        table.with(Style::blank());
+        table.modify(Rows::first(), Color::BOLD);
+        table.modify(Columns::new(3..), Alignment::right());
+        table.with(
+            Highlight::new(Rows::single(1)).color(BorderColor::default().top(Color::FG_WHITE)),
+        );
+        table.with(Highlight::new(Rows::single(1)).border(Border::new().top('━')));
+        println!("\n{table}\n");
+        Ok(())
+    }
+
+    /// Symbol from the token metadata of a mint, empty when it has none.
+    async fn get_mint_symbol(&self, mint: Pubkey) -> String {
+        let metadata_address = mpl_token_metadata::accounts::Metadata::find_pda(&mint).0;
+        match self.rpc_client.get_account_data(&metadata_address).await {
+            Ok(data) => mpl_token_metadata::accounts::Metadata::from_bytes(&data)
+                .map(|metadata| metadata.symbol.trim_matches('\0').trim().to_string())
+                .unwrap_or_default(),
+            Err(_) => String::new(),
+        }
+    }
+}
+
+fn pool_from_url(pool_url: Option<String>) -> Result<Pool, Error> {
+    match pool_url {
+        Some(pool_url) => Ok(Pool {
+            http_client: reqwest::Client::new(),
+            pool_url,
+        }),
+        None => Err(Error::Internal("Pool URL is required".to_string())),
+    }
+}
+
+#[derive(Tabled)]
+pub struct PoolShareTableData {
+    #[tabled(rename = "Pool")]
+    pub pool: String,
+    #[tabled(rename = "Mint")]
+    pub mint: String,
+    #[tabled(rename = "Symbol")]
+    pub symbol: String,
+    #[tabled(rename = "Pool deposits")]
+    pub pool_deposits: String,
+    #[tabled(rename = "My shares")]
+    pub my_shares: String,
+    #[tabled(rename = "My share")]
+    pub my_share: String,
+}
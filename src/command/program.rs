@@ -1,4 +1,5 @@
 use eore_api::consts::{BUS_ADDRESSES, EPOCH_DURATION, TREASURY_ADDRESS, TREASURY_TOKENS_ADDRESS};
+use serde::Serialize;
 use tabled::{
     settings::{
         object::{Columns, Rows},
@@ -9,99 +10,178 @@ use tabled::{
 
 use crate::{
     utils::{
-        amount_u64_to_f64, format_timestamp, get_bus, get_config, TableData, TableSectionTitle,
+        amount_u64_to_f64, format_timestamp, get_bus, get_config, print_json, OutputFormat,
+        TableData, TableSectionTitle, TokenAmount,
     },
     Miner,
 };
 
+/// JSON output of `program`.
+#[derive(Serialize)]
+struct ProgramOutput {
+    /// Seconds.
+    epoch_duration: i64,
+    /// Unix timestamp.
+    epoch_start_at: i64,
+    min_difficulty: u64,
+    busses: Vec<BusOutput>,
+    /// Reward per solution of each difficulty, from the min difficulty up to
+    /// the first one that earns the max reward.
+    reward_rates: Vec<RewardRateOutput>,
+    treasury: TreasuryOutput,
+}
+
+#[derive(Serialize)]
+struct BusOutput {
+    id: u64,
+    address: String,
+    rewards: TokenAmount,
+}
+
+#[derive(Serialize)]
+struct RewardRateOutput {
+    difficulty: u32,
+    reward: TokenAmount,
+}
+
+#[derive(Serialize)]
+struct TreasuryOutput {
+    address: String,
+    balance: TokenAmount,
+}
+
 impl Miner {
     pub async fn program(&self) {
         // Aggregate data
-        let mut data = vec![];
-        self.fetch_config_data(&mut data).await;
-        let len1 = data.len();
-        self.fetch_busses_data(&mut data).await;
-        let len2 = data.len();
-        self.fetch_rewards_data(&mut data).await;
-        let len3 = data.len();
-        self.fetch_treasury_data(&mut data).await;
-
-        // Build table
-        let mut table = Table::new(data);
-        table.with(Remove::row(Rows::first()));
-        table.modify(Columns::single(1), Alignment::right());
-        table.with(Style::blank());
-        table.section_title(0, "Config");
-        table.section_title(len1, "Busses");
-        table.section_title(len2, "Reward rates");
-        table.section_title(len3, "Treasury");
-        println!("{table}\n");
-    }
+        let config = get_config(&self.rpc_client)
+            .await
+            .expect("Failed to fetch config account");
+        let program = ProgramOutput {
+            epoch_duration: EPOCH_DURATION,
+            epoch_start_at: config.last_reset_at,
+            min_difficulty: config.min_difficulty,
+            busses: self.fetch_busses().await,
+            reward_rates: reward_rates(config.base_reward_rate, config.min_difficulty),
+            treasury: self.fetch_treasury().await,
+        };
+        if self.output == OutputFormat::Json {
+            return print_json(&program);
+        }
 
-    async fn fetch_config_data(&self, data: &mut Vec<TableData>) {
-        let config = get_config(&self.rpc_client).await;
+        // Config
+        let mut data = vec![];
         data.push(TableData {
             key: "Epoch duration".to_string(),
-            value: format!("{} sec", EPOCH_DURATION),
+            value: format!("{} sec", program.epoch_duration),
         });
         data.push(TableData {
             key: "Epoch start at".to_string(),
-            value: format_timestamp(config.last_reset_at),
+            value: format_timestamp(program.epoch_start_at),
         });
         data.push(TableData {
             key: "Min difficulty".to_string(),
-            value: config.min_difficulty.to_string(),
+            value: program.min_difficulty.to_string(),
         });
-    }
 
-    async fn fetch_busses_data(&self, data: &mut Vec<TableData>) {
-        for address in BUS_ADDRESSES.iter() {
-            let bus = get_bus(&self.rpc_client, *address)
-                .await
-                .expect("Failed to fetch bus account");
-            let rewards = amount_u64_to_f64(bus.rewards);
+        // Busses
+        let len1 = data.len();
+        for bus in program.busses.iter() {
             data.push(TableData {
                 key: format!("{}", bus.id),
-                value: format!("{:#.11} BITZ", rewards),
+                value: format!("{:#.11} BITZ", amount_u64_to_f64(bus.rewards.amount)),
             });
         }
-    }
 
-    async fn fetch_rewards_data(&self, data: &mut Vec<TableData>) {
-        let config = get_config(&self.rpc_client).await;
-        for i in 0..32 {
-            let reward_rate = config
-                .base_reward_rate
-                .saturating_mul(2u64.saturating_pow(i));
-            let amount = amount_u64_to_f64(reward_rate).min(1.0);
+        // Reward rates
+        let len2 = data.len();
+        for rate in program.reward_rates.iter() {
+            let amount = amount_u64_to_f64(rate.reward.amount);
             data.push(TableData {
                 key: format!(
                     "{}{}",
-                    config.min_difficulty as u32 + i,
+                    rate.difficulty,
                     if amount >= 1.0 { "+" } else { "" }
                 ),
                 value: format!("{:#.11} BITZ", amount),
             });
-            if amount >= 1.0 {
-                break;
-            }
         }
+
+        // Treasury
+        let len3 = data.len();
+        data.push(TableData {
+            key: "Address".to_string(),
+            value: program.treasury.address.clone(),
+        });
+        data.push(TableData {
+            key: "Balance".to_string(),
+            value: format!(
+                "{} BITZ",
+                spl_token::amount_to_ui_amount(
+                    program.treasury.balance.amount,
+                    program.treasury.balance.decimals
+                )
+            ),
+        });
+
+        // Build table
+        let mut table = Table::new(data);
+        table.with(Remove::row(Rows::first()));
+        table.modify(Columns::single(1), Alignment::right());
+        table.with(Style::blank());
+        table.section_title(0, "Config");
+        table.section_title(len1, "Busses");
+        table.section_title(len2, "Reward rates");
+        table.section_title(len3, "Treasury");
+        println!("{table}\n");
+    }
+
+    async fn fetch_busses(&self) -> Vec<BusOutput> {
+        let mut busses = vec![];
+        for address in BUS_ADDRESSES.iter() {
+            let bus = get_bus(&self.rpc_client, *address)
+                .await
+                .expect("Failed to fetch bus account");
+            busses.push(BusOutput {
+                id: bus.id,
+                address: address.to_string(),
+                rewards: TokenAmount::bitz(bus.rewards),
+            });
+        }
+        busses
     }
 
-    async fn fetch_treasury_data(&self, data: &mut Vec<TableData>) {
+    async fn fetch_treasury(&self) -> TreasuryOutput {
         let token_balance = self
             .rpc_client
             .get_token_account(&TREASURY_TOKENS_ADDRESS)
             .await
             .expect("Failed to fetch treasury tokens account")
             .expect("Failed to fetch treasury tokens account");
-        data.push(TableData {
-            key: "Address".to_string(),
-            value: TREASURY_ADDRESS.to_string(),
-        });
-        data.push(TableData {
-            key: "Balance".to_string(),
-            value: format!("{} BITZ", token_balance.token_amount.ui_amount_string),
+        TreasuryOutput {
+            address: TREASURY_ADDRESS.to_string(),
+            balance: TokenAmount::new(
+                token_balance.token_amount.amount.parse().unwrap_or(0),
+                token_balance.token_amount.decimals,
+            ),
+        }
+    }
+}
+
+/// Rewards double with each difficulty above the min, capped at one BITZ.
+fn reward_rates(base_reward_rate: u64, min_difficulty: u64) -> Vec<RewardRateOutput> {
+    let max_reward = 10u64.pow(eore_api::consts::TOKEN_DECIMALS as u32);
+    let mut rates = vec![];
+    for i in 0..32 {
+        let reward = base_reward_rate
+            .saturating_mul(2u64.saturating_pow(i))
+            .min(max_reward);
+        rates.push(RewardRateOutput {
+            difficulty: min_difficulty as u32 + i,
+            reward: TokenAmount::bitz(reward),
         });
+        if reward >= max_reward {
+            break;
+        }
     }
+    rates
 }
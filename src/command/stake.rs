@@ -3,6 +3,7 @@ use std::str::FromStr;
 use colored::*;
 use eore_api::state::Proof;
 use eore_boost_api::state::{boost_pda, stake_pda, Boost, Config as BoostConfig, Stake};
+use serde::Serialize;
 use solana_program::{program_pack::Pack, pubkey::Pubkey};
 use solana_sdk::signature::Signer;
 use spl_token::{amount_to_ui_amount, state::Mint};
@@ -21,12 +22,69 @@ use crate::{
     error::Error,
     utils::{
         amount_u64_to_f64, format_timestamp, get_boost, get_boost_config, get_boost_stake_accounts,
-        get_boosts, get_mint, get_proof_with_authority, get_stake, ComputeBudget, TableData,
-        TableSectionTitle,
+        get_boosts, get_mint, get_proof_with_authority, get_stake, print_json, ComputeBudget,
+        OutputFormat, TableData, TableSectionTitle, TokenAmount,
     },
     Miner, StakeAccountsArgs,
 };
 
+/// JSON output of `stake`, one per boost. Shares are fractions between 0 and
+/// 1, `null` when the boost has no deposits.
+#[derive(Serialize)]
+struct StakeListOutput {
+    boost: String,
+    mint: String,
+    symbol: String,
+    weight: u64,
+    total_deposits: TokenAmount,
+    total_stakers: u64,
+    deposits: TokenAmount,
+    share: Option<f64>,
+    claimable_yield: TokenAmount,
+}
+
+/// JSON output of `stake --mint`.
+#[derive(Serialize)]
+struct StakeGetOutput {
+    boost: BoostOutput,
+    stake_address: String,
+    /// `null` when the authority has no stake in the boost.
+    stake: Option<StakeOutput>,
+}
+
+#[derive(Serialize)]
+struct BoostOutput {
+    address: String,
+    mint: String,
+    symbol: String,
+    /// Unix timestamp.
+    expires_at: i64,
+    weight: u64,
+    total_deposits: TokenAmount,
+    total_stakers: u64,
+}
+
+#[derive(Serialize)]
+struct StakeOutput {
+    deposits: TokenAmount,
+    share: Option<f64>,
+    /// Unix timestamps.
+    last_claim_at: i64,
+    last_deposit_at: i64,
+    last_withdraw_at: i64,
+    claimable_yield: TokenAmount,
+}
+
+/// JSON output of `stake accounts`, one per staker.
+#[derive(Serialize)]
+struct StakerOutput {
+    address: String,
+    authority: String,
+    deposits: TokenAmount,
+    share: Option<f64>,
+    rewards: TokenAmount,
+}
+
 impl Miner {
     pub async fn stake(&self, args: StakeArgs) {
         if let Some(subcommand) = args.command.clone() {
@@ -179,12 +237,22 @@ impl Miner {
 
         // Aggregate data
         let mut data = vec![];
-        self.fetch_boost_data(boost_address, boost, mint, symbol.clone(), &mut data)
+        let boost_output = self
+            .fetch_boost_data(boost_address, boost, mint, symbol.clone(), &mut data)
             .await;
         let len1 = data.len();
-        self.fetch_stake_data(stake_address, boost, mint, symbol.clone(), &mut data)
+        let stake_output = self
+            .fetch_stake_data(stake_address, boost, mint, symbol.clone(), &mut data)
             .await;
         let _len2 = data.len();
+        if self.output == OutputFormat::Json {
+            print_json(&StakeGetOutput {
+                boost: boost_output,
+                stake_address: stake_address.to_string(),
+                stake: stake_output,
+            });
+            return Ok(());
+        }
 
         // Build table
         let mut table = Table::new(data);
@@ -204,7 +272,7 @@ impl Miner {
         mint: Mint,
         symbol: String,
         data: &mut Vec<TableData>,
-    ) {
+    ) -> Option<StakeOutput> {
         let boost_config_address = eore_boost_api::state::config_pda().0;
         let stake = get_stake(&self.rpc_client, address).await;
         let boost_config = get_boost_config(&self.rpc_client).await;
@@ -250,11 +318,20 @@ impl Miner {
                     format!("{} BITZ", amount_u64_to_f64(claimable_yield))
                 },
             });
+            Some(StakeOutput {
+                deposits: TokenAmount::new(stake.balance, mint.decimals),
+                share: share(stake.balance, boost.total_deposits),
+                last_claim_at: stake.last_claim_at,
+                last_deposit_at: stake.last_deposit_at,
+                last_withdraw_at: stake.last_withdraw_at,
+                claimable_yield: TokenAmount::bitz(claimable_yield),
+            })
         } else {
             data.push(TableData {
                 key: "Status".to_string(),
                 value: "Not found".red().bold().to_string(),
             });
+            None
         }
     }
 
@@ -265,7 +342,7 @@ impl Miner {
         mint: Mint,
         symbol: String,
         data: &mut Vec<TableData>,
-    ) {
+    ) -> BoostOutput {
         data.push(TableData {
             key: "Address".to_string(),
             value: address.to_string(),
@@ -294,6 +371,15 @@ impl Miner {
             key: "Total stakers".to_string(),
             value: boost.total_stakers.to_string(),
         });
+        BoostOutput {
+            address: address.to_string(),
+            mint: boost.mint.to_string(),
+            symbol: symbol.trim().to_string(),
+            expires_at: boost.expires_at,
+            weight: boost.weight,
+            total_deposits: TokenAmount::new(boost.total_deposits, mint.decimals),
+            total_stakers: boost.total_stakers,
+        }
     }
 
     async fn stake_list(&self, args: StakeArgs) -> Result<(), Error> {
@@ -313,6 +399,7 @@ impl Miner {
 
         // Iterate over all boosts
         let mut data = vec![];
+        let mut output = vec![];
         let boosts = get_boosts(&self.rpc_client)
             .await
             .expect("Failed to fetch boosts");
@@ -348,6 +435,17 @@ impl Miner {
             };
 
             // Aggregate data
+            output.push(StakeListOutput {
+                boost: address.to_string(),
+                mint: boost.mint.to_string(),
+                symbol: symbol.trim().to_string(),
+                weight: boost.weight,
+                total_deposits: TokenAmount::new(boost.total_deposits, mint.decimals),
+                total_stakers: boost.total_stakers,
+                deposits: TokenAmount::new(stake_balance, mint.decimals),
+                share: share(stake_balance, boost.total_deposits),
+                claimable_yield: TokenAmount::bitz(stake_rewards),
+            });
             data.push(StakeTableData {
                 mint: boost.mint.to_string(),
                 symbol,
@@ -376,6 +474,10 @@ impl Miner {
                 },
             });
         }
+        if self.output == OutputFormat::Json {
+            print_json(&output);
+            return Ok(());
+        }
 
         // Build table
         let mut table = Table::new(data);
@@ -663,6 +765,20 @@ impl Miner {
             .expect("Failed to fetch stake accounts");
         stake_accounts
             .sort_by(|(_addr1, stake1), (_addr2, stake2)| stake2.balance.cmp(&stake1.balance));
+        if self.output == OutputFormat::Json {
+            let output: Vec<_> = stake_accounts
+                .iter()
+                .map(|(stake_address, stake)| StakerOutput {
+                    address: stake_address.to_string(),
+                    authority: stake.authority.to_string(),
+                    deposits: TokenAmount::new(stake.balance, mint_account.decimals),
+                    share: share(stake.balance, boost.total_deposits),
+                    rewards: TokenAmount::bitz(stake.rewards),
+                })
+                .collect();
+            print_json(&output);
+            return Ok(());
+        }
         let mut data = vec![];
         for (_stake_address, stake) in stake_accounts {
             data.push(StakerTableData {
@@ -698,6 +814,11 @@ impl Miner {
     }
 }
 
+/// Fraction of the total deposits, `None` when nothing is deposited.
+fn share(balance: u64, total_deposits: u64) -> Option<f64> {
+    (total_deposits > 0).then(|| balance as f64 / total_deposits as f64)
+}
+
 pub fn calculate_claimable_yield(
     boost: Boost,
     boost_config: BoostConfig,
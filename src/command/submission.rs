@@ -0,0 +1,224 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use drillx::Solution;
+use tokio::sync::Notify;
+
+use crate::{error::Error, Miner};
+
+use super::pool::Pool;
+
+/// Delay before the first retry of a failed submission, doubled on each retry.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Longest delay between retries of a failed submission.
+const MAX_BACKOFF: Duration = Duration::from_secs(4);
+
+/// How often `flush` checks whether the round has settled.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Outcomes of pool submissions over the whole session.
+#[derive(Default)]
+pub struct SubmissionStats {
+    /// Solutions the pool accepted.
+    pub accepted: AtomicU64,
+    /// Solutions the pool refused, which are not retried.
+    pub rejected: AtomicU64,
+    /// Solutions dropped because their challenge expired or was replaced.
+    pub late: AtomicU64,
+}
+
+impl fmt::Display for SubmissionStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Submissions: {} accepted, {} rejected, {} late",
+            self.accepted.load(Ordering::Relaxed),
+            self.rejected.load(Ordering::Relaxed),
+            self.late.load(Ordering::Relaxed)
+        )
+    }
+}
+
+/// Submissions for the challenge currently being collected.
+struct Round {
+    challenge: [u8; 32],
+    expires_at: Instant,
+    /// Best solution waiting to be posted.
+    pending: Option<(Solution, u32)>,
+    /// Difficulty of the best solution the pool accepted.
+    accepted: Option<u32>,
+    /// Difficulty of the solution being posted.
+    in_flight: Option<u32>,
+}
+
+/// Posts solutions to the active pool from a background task. Only the best
+/// solution of the current challenge is kept, so the queue never holds more
+/// than one. Failed posts are retried with backoff until the challenge expires.
+#[derive(Clone)]
+pub struct SubmissionQueue {
+    round: Arc<Mutex<Option<Round>>>,
+    pool: Arc<RwLock<Pool>>,
+    queued: Arc<Notify>,
+    pub stats: Arc<SubmissionStats>,
+}
+
+impl SubmissionQueue {
+    pub fn new(miner: Miner, pool: Pool) -> Self {
+        let queue = Self {
+            round: Arc::new(Mutex::new(None)),
+            pool: Arc::new(RwLock::new(pool)),
+            queued: Arc::new(Notify::new()),
+            stats: Arc::new(SubmissionStats::default()),
+        };
+        tokio::spawn(queue.clone().run(miner));
+        queue
+    }
+
+    /// Sends further submissions to another pool.
+    pub fn set_pool(&self, pool: Pool) {
+        *self.pool.write().unwrap() = pool;
+    }
+
+    /// Starts collecting on a new challenge. A solution still waiting for the
+    /// previous challenge is dropped.
+    pub fn start_round(&self, challenge: [u8; 32], expires_at: Instant) {
+        let mut round = self.round.lock().unwrap();
+        if round.as_ref().is_some_and(|round| round.pending.is_some()) {
+            self.stats.late.fetch_add(1, Ordering::Relaxed);
+        }
+        *round = Some(Round {
+            challenge,
+            expires_at,
+            pending: None,
+            accepted: None,
+            in_flight: None,
+        });
+    }
+
+    /// Queues a solution unless a solution at least as good has already been
+    /// queued or posted for its challenge.
+    pub fn push(&self, challenge: [u8; 32], solution: Solution) {
+        let difficulty = solution.to_hash().difficulty();
+        let mut guard = self.round.lock().unwrap();
+        let Some(round) = guard.as_mut().filter(|round| round.challenge == challenge) else {
+            self.stats.late.fetch_add(1, Ordering::Relaxed);
+            return;
+        };
+        let best = [
+            round.accepted,
+            round.in_flight,
+            round.pending.as_ref().map(|(_, difficulty)| *difficulty),
+        ]
+        .into_iter()
+        .flatten()
+        .max();
+        if best.is_some_and(|best| best >= difficulty) {
+            return;
+        }
+        round.pending = Some((solution, difficulty));
+        drop(guard);
+        self.queued.notify_one();
+    }
+
+    /// Waits until the current round has nothing left to post or it expires.
+    /// Returns the difficulty of the best accepted solution, if any.
+    pub async fn flush(&self) -> Option<u32> {
+        loop {
+            {
+                let round = self.round.lock().unwrap();
+                let round = round.as_ref()?;
+                let settled = round.pending.is_none() && round.in_flight.is_none();
+                if settled || Instant::now() >= round.expires_at {
+                    return round.accepted;
+                }
+            }
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+        }
+    }
+
+    async fn run(self, miner: Miner) {
+        loop {
+            // Take the best queued solution
+            let Some((challenge, expires_at, solution, difficulty)) = self.take() else {
+                self.queued.notified().await;
+                continue;
+            };
+
+            // Post it, retrying until the challenge expires or a better one is queued
+            let pool = self.pool.read().unwrap().clone();
+            let mut attempt = 0;
+            let accepted = loop {
+                match pool.post_pool_solution(&miner, &solution).await {
+                    Ok(()) => {
+                        self.stats.accepted.fetch_add(1, Ordering::Relaxed);
+                        break true;
+                    }
+                    Err(err) if is_rejection(&err) => {
+                        self.stats.rejected.fetch_add(1, Ordering::Relaxed);
+                        break false;
+                    }
+                    Err(_err) => {
+                        let backoff = INITIAL_BACKOFF
+                            .saturating_mul(2u32.saturating_pow(attempt))
+                            .min(MAX_BACKOFF);
+                        attempt += 1;
+                        if Instant::now() + backoff >= expires_at {
+                            self.stats.late.fetch_add(1, Ordering::Relaxed);
+                            break false;
+                        }
+                        if self.is_superseded(challenge) {
+                            break false;
+                        }
+                        tokio::time::sleep(backoff).await;
+                    }
+                }
+            };
+
+            // Settle the round
+            let mut round = self.round.lock().unwrap();
+            if let Some(round) = round.as_mut().filter(|round| round.challenge == challenge) {
+                round.in_flight = None;
+                if accepted {
+                    round.accepted = round.accepted.max(Some(difficulty));
+                }
+            }
+        }
+    }
+
+    fn take(&self) -> Option<([u8; 32], Instant, Solution, u32)> {
+        let mut round = self.round.lock().unwrap();
+        let round = round.as_mut()?;
+        let (solution, difficulty) = round.pending.take()?;
+        if Instant::now() >= round.expires_at {
+            self.stats.late.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        round.in_flight = Some(difficulty);
+        Some((round.challenge, round.expires_at, solution, difficulty))
+    }
+
+    /// Whether the challenge was replaced or a better solution is waiting.
+    fn is_superseded(&self, challenge: [u8; 32]) -> bool {
+        let round = self.round.lock().unwrap();
+        match round.as_ref() {
+            Some(round) => round.challenge != challenge || round.pending.is_some(),
+            None => true,
+        }
+    }
+}
+
+/// The pool answered with a client error, so retrying the same solution will
+/// not help. Other errors are network failures or server errors.
+fn is_rejection(err: &Error) -> bool {
+    match err {
+        Error::Reqwest(err) => err.status().is_some_and(|status| status.is_client_error()),
+        _ => false,
+    }
+}
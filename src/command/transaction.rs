@@ -1,92 +1,341 @@
-use std::str::FromStr;
+use std::{collections::BTreeMap, str::FromStr};
 
-use b64::FromBase64;
 use colored::Colorize;
-use eore_api::event::MineEvent;
-use solana_sdk::signature::Signature;
-use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
+use serde::Serialize;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_program::{native_token::lamports_to_sol, pubkey::Pubkey};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding, UiTransactionTokenBalance};
 use tabled::{settings::{object::{Columns, Rows}, Alignment, Remove, Style}, Table};
 
-use crate::{error::Error, utils::{amount_u64_to_string, format_timestamp, TableData, TableSectionTitle}, Miner, TransactionArgs};
+use crate::{error::Error, utils::{amount_u64_to_string, decode_instruction, describe_transaction_error, format_timestamp, mine_event_from_logs, print_json, OutputFormat, TableData, TableSectionTitle, TokenAmount}, Miner, TransactionArgs};
+
+/// JSON output of `transaction`.
+#[derive(Serialize)]
+struct TransactionOutput {
+    signature: String,
+    slot: u64,
+    /// Unix timestamp.
+    block_time: Option<i64>,
+    fee_payer: String,
+    fee: TokenAmount,
+    compute_units: Option<u64>,
+    /// `confirmed` or `failed`.
+    status: &'static str,
+    error: Option<String>,
+    mine_event: Option<MineEventOutput>,
+    instructions: Vec<InstructionOutput>,
+    balance_changes: Vec<BalanceChangeOutput>,
+}
+
+#[derive(Serialize)]
+struct MineEventOutput {
+    difficulty: u64,
+    base_reward: TokenAmount,
+    boost_reward: TokenAmount,
+    total_reward: TokenAmount,
+    /// Seconds between the solution and the previous one.
+    timing: i64,
+}
+
+#[derive(Serialize)]
+struct InstructionOutput {
+    program_id: String,
+    program: String,
+    name: String,
+    /// Decoded arguments, formatted for display.
+    args: BTreeMap<String, String>,
+    accounts: Vec<AccountMetaOutput>,
+}
+
+#[derive(Serialize)]
+struct AccountMetaOutput {
+    address: String,
+    signer: bool,
+    writable: bool,
+}
+
+/// A change of an ETH balance, when `mint` is `null`, or of a token balance,
+/// in the smallest unit. `address` is the token account owner if known.
+#[derive(Serialize)]
+struct BalanceChangeOutput {
+    address: String,
+    mint: Option<String>,
+    change: i128,
+    decimals: u8,
+}
 
 impl Miner {
     pub async fn transaction(&self, args: TransactionArgs) -> Result<(), Error> {
         let signature = args.signature;
         let signature = Signature::from_str(&signature).expect("Failed to parse signature");
-        match self.rpc_client.get_transaction(&signature, UiTransactionEncoding::Json).await {
-            Ok(tx) => {
-                let mut data = vec![];
-                
-                // Parse transaction response
-                if let Some(meta) = tx.transaction.meta {
-                    if let OptionSerializer::Some(log_messages) = meta.log_messages {
-                        if let Some(return_log) = log_messages.iter().find(|log| log.starts_with("Program return: ")) {
-                            if let Some(return_data) = return_log.strip_prefix(&format!("Program return: {} ", eore_api::ID)) {
-                                if let Ok(return_data) = return_data.from_base64() {
-                                    let event = MineEvent::from_bytes(&return_data);
-                                    data.push(TableData {
-                                        key: "Signature".to_string(),
-                                        value: signature.to_string(),
-                                    });
-                                    data.push(TableData {
-                                        key: "Block".to_string(),
-                                        value: tx.slot.to_string(),
-                                    });
-                                    data.push(TableData {
-                                        key: "Timestamp".to_string(),
-                                        value: format_timestamp(tx.block_time.unwrap_or_default()),
-                                    });
-                                    data.push(TableData {
-                                        key: "Difficulty".to_string(),
-                                        value: event.difficulty.to_string(),
-                                    });
-                                    data.push(TableData {
-                                        key: "Base Reward".to_string(),
-                                        value: amount_u64_to_string(event.net_base_reward),
-                                    });
-                                    data.push(TableData {
-                                        key: "Boost Reward".to_string(),
-                                        value: amount_u64_to_string(event.net_miner_boost_reward),
-                                    });
-                                    data.push(TableData {
-                                        key: "Total Reward".to_string(),
-                                        value: amount_u64_to_string(event.net_reward),
-                                    });
-                                    data.push(TableData {
-                                        key: "Timing".to_string(),
-                                        value: format!("{}s", event.timing),
-                                    });
-                                    data.push(TableData {
-                                        key: "Status".to_string(),
-                                        value: match meta.status {
-                                            Ok(()) => "Confirmed".bold().green().to_string(),
-                                            Err(_e) => "Failed".bold().red().to_string(),
-                                        },
-                                    });
-
-                                }
-                            }
-                        }
-                    }
-                }
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+        let tx = match self.rpc_client.get_transaction_with_config(&signature, config).await {
+            Ok(tx) => tx,
+            Err(e) => {
+                println!("Error: {:?}", e);
+                return Ok(());
+            }
+        };
+        let Some(meta) = tx.transaction.meta else {
+            return Err(Error::Internal("Transaction metadata is not available".to_string()));
+        };
+        let Some(versioned) = tx.transaction.transaction.decode() else {
+            return Err(Error::Internal("Failed to decode transaction".to_string()));
+        };
+        let message = &versioned.message;
+
+        // Resolve account keys, including those loaded from lookup tables
+        let mut account_keys = message.static_account_keys().to_vec();
+        if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+            for address in loaded.writable.iter().chain(loaded.readonly.iter()) {
+                account_keys.push(Pubkey::from_str(address)?);
+            }
+        }
+        let key = |index: usize| account_keys.get(index).copied().unwrap_or_default();
+
+        let program_ids: Vec<Pubkey> = message
+            .instructions()
+            .iter()
+            .map(|ix| key(ix.program_id_index as usize))
+            .collect();
+
+        let mut output = TransactionOutput {
+            signature: signature.to_string(),
+            slot: tx.slot,
+            block_time: tx.block_time,
+            fee_payer: key(0).to_string(),
+            fee: TokenAmount::eth(meta.fee),
+            compute_units: None,
+            status: if meta.status.is_ok() { "confirmed" } else { "failed" },
+            error: meta.status.as_ref().err().map(|err| describe_transaction_error(err, &program_ids)),
+            mine_event: None,
+            instructions: vec![],
+            balance_changes: vec![],
+        };
+
+        // Summary
+        let mut data = vec![];
+        data.push(TableData {
+            key: "Signature".to_string(),
+            value: signature.to_string(),
+        });
+        data.push(TableData {
+            key: "Block".to_string(),
+            value: tx.slot.to_string(),
+        });
+        data.push(TableData {
+            key: "Timestamp".to_string(),
+            value: format_timestamp(tx.block_time.unwrap_or_default()),
+        });
+        data.push(TableData {
+            key: "Fee payer".to_string(),
+            value: key(0).to_string(),
+        });
+        data.push(TableData {
+            key: "Fee".to_string(),
+            value: format!("{} ETH", lamports_to_sol(meta.fee)),
+        });
+        if let OptionSerializer::Some(units) = meta.compute_units_consumed {
+            output.compute_units = Some(units);
+            data.push(TableData {
+                key: "Compute units".to_string(),
+                value: units.to_string(),
+            });
+        }
+        data.push(TableData {
+            key: "Status".to_string(),
+            value: match &meta.status {
+                Ok(()) => "Confirmed".bold().green().to_string(),
+                Err(err) => format!(
+                    "{} {}",
+                    "Failed".bold().red(),
+                    describe_transaction_error(err, &program_ids)
+                ),
+            },
+        });
+
+        // Mine event
+        let mut mine_event_row = None;
+        if let OptionSerializer::Some(log_messages) = &meta.log_messages {
+            if let Some(event) = mine_event_from_logs(log_messages) {
+                mine_event_row = Some(data.len());
+                output.mine_event = Some(MineEventOutput {
+                    difficulty: event.difficulty,
+                    base_reward: TokenAmount::bitz(event.net_base_reward),
+                    boost_reward: TokenAmount::bitz(event.net_miner_boost_reward),
+                    total_reward: TokenAmount::bitz(event.net_reward),
+                    timing: event.timing,
+                });
+                data.push(TableData {
+                    key: "Difficulty".to_string(),
+                    value: event.difficulty.to_string(),
+                });
+                data.push(TableData {
+                    key: "Base Reward".to_string(),
+                    value: amount_u64_to_string(event.net_base_reward),
+                });
+                data.push(TableData {
+                    key: "Boost Reward".to_string(),
+                    value: amount_u64_to_string(event.net_miner_boost_reward),
+                });
+                data.push(TableData {
+                    key: "Total Reward".to_string(),
+                    value: amount_u64_to_string(event.net_reward),
+                });
+                data.push(TableData {
+                    key: "Timing".to_string(),
+                    value: format!("{}s", event.timing),
+                });
+            }
+        }
 
-                // Check if data is empty
-                if data.is_empty() {
-                    return Err(Error::Internal("Unknown transaction".to_string())).map_err(From::from);
+        // Instructions
+        let mut instruction_rows = vec![];
+        for (i, ix) in message.instructions().iter().enumerate() {
+            let program_id = key(ix.program_id_index as usize);
+            let decoded = decode_instruction(&program_id, &ix.data);
+            instruction_rows.push((data.len(), format!("#{} {} {}", i + 1, decoded.program, decoded.name)));
+            data.push(TableData {
+                key: "Program".to_string(),
+                value: program_id.to_string(),
+            });
+            let mut instruction = InstructionOutput {
+                program_id: program_id.to_string(),
+                program: decoded.program.clone(),
+                name: decoded.name.clone(),
+                args: decoded.args.iter().cloned().collect(),
+                accounts: vec![],
+            };
+            for (key, value) in decoded.args {
+                data.push(TableData { key, value });
+            }
+            for (j, index) in ix.accounts.iter().enumerate() {
+                let index = *index as usize;
+                let signer = message.is_signer(index);
+                let writable = message.is_maybe_writable(index, None);
+                instruction.accounts.push(AccountMetaOutput {
+                    address: key(index).to_string(),
+                    signer,
+                    writable,
+                });
+                let mut flags = vec![];
+                if signer {
+                    flags.push("signer");
+                }
+                if writable {
+                    flags.push("writable");
                 }
+                data.push(TableData {
+                    key: format!("Account {}", j),
+                    value: if flags.is_empty() {
+                        key(index).to_string()
+                    } else {
+                        format!("{} ({})", key(index), flags.join(", "))
+                    },
+                });
+            }
+            output.instructions.push(instruction);
+        }
 
-                // Print table
-                let mut table = Table::new(data);
-                table.with(Remove::row(Rows::first()));
-                table.modify(Columns::single(1), Alignment::right());
-                table.with(Style::blank());
-                table.section_title(0, "Transaction");
-                println!("{table}\n");
+        // Balance changes
+        let balance_row = data.len();
+        for (index, (pre, post)) in meta.pre_balances.iter().zip(meta.post_balances.iter()).enumerate() {
+            if pre != post {
+                output.balance_changes.push(BalanceChangeOutput {
+                    address: key(index).to_string(),
+                    mint: None,
+                    change: *post as i128 - *pre as i128,
+                    decimals: 9,
+                });
+                data.push(TableData {
+                    key: key(index).to_string(),
+                    value: format!("{:+} ETH", lamports_to_sol(*post) - lamports_to_sol(*pre)),
+                });
             }
-            Err(e) => {
-                println!("Error: {:?}", e);
+        }
+        if let (OptionSerializer::Some(pre), OptionSerializer::Some(post)) = (&meta.pre_token_balances, &meta.post_token_balances) {
+            for balance in post {
+                let before = pre.iter().find(|b| b.account_index == balance.account_index);
+                let change = token_amount(Some(balance)) - token_amount(before);
+                if change != 0.0 {
+                    let owner = match &balance.owner {
+                        OptionSerializer::Some(owner) => owner.clone(),
+                        _ => key(balance.account_index as usize).to_string(),
+                    };
+                    output.balance_changes.push(BalanceChangeOutput {
+                        address: owner.clone(),
+                        mint: Some(balance.mint.clone()),
+                        change: raw_token_amount(Some(balance)) - raw_token_amount(before),
+                        decimals: balance.ui_token_amount.decimals,
+                    });
+                    data.push(TableData {
+                        key: owner,
+                        value: format!("{:+} {}", change, token_symbol(&balance.mint)),
+                    });
+                }
+            }
+            for balance in pre {
+                if !post.iter().any(|b| b.account_index == balance.account_index) {
+                    output.balance_changes.push(BalanceChangeOutput {
+                        address: key(balance.account_index as usize).to_string(),
+                        mint: Some(balance.mint.clone()),
+                        change: -raw_token_amount(Some(balance)),
+                        decimals: balance.ui_token_amount.decimals,
+                    });
+                    data.push(TableData {
+                        key: key(balance.account_index as usize).to_string(),
+                        value: format!("{:+} {}", -token_amount(Some(balance)), token_symbol(&balance.mint)),
+                    });
+                }
             }
         }
+        let has_balance_changes = data.len() > balance_row;
+        if self.output == OutputFormat::Json {
+            print_json(&output);
+            return Ok(());
+        }
+
+        // Print table
+        let mut table = Table::new(data);
+        table.with(Remove::row(Rows::first()));
+        table.modify(Columns::single(1), Alignment::right());
+        table.with(Style::blank());
+        table.section_title(0, "Transaction");
+        if let Some(row) = mine_event_row {
+            table.section_title(row, "Mine event");
+        }
+        for (row, title) in instruction_rows.iter() {
+            table.section_title(*row, title);
+        }
+        if has_balance_changes {
+            table.section_title(balance_row, "Balance changes");
+        }
+        println!("{table}\n");
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+fn token_amount(balance: Option<&UiTransactionTokenBalance>) -> f64 {
+    balance
+        .and_then(|balance| balance.ui_token_amount.ui_amount)
+        .unwrap_or(0.0)
+}
+
+fn raw_token_amount(balance: Option<&UiTransactionTokenBalance>) -> i128 {
+    balance
+        .and_then(|balance| balance.ui_token_amount.amount.parse().ok())
+        .unwrap_or(0)
+}
+
+fn token_symbol(mint: &str) -> String {
+    if mint == eore_api::consts::MINT_ADDRESS.to_string() {
+        "BITZ".to_string()
+    } else {
+        mint.to_string()
+    }
+}
@@ -0,0 +1,330 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use clap::{parser::ValueSource, ArgMatches, Command};
+use colored::*;
+use serde::Deserialize;
+use tabled::{
+    settings::{object::Rows, Remove, Style},
+    Table,
+};
+
+use crate::utils::{TableData, TableSectionTitle};
+
+/// Name of the config file, looked up in the working directory and then in
+/// `~/.config/bitz/`. `BITZ_CONFIG` overrides the path.
+pub const CONFIG_FILE: &str = "bitz.toml";
+
+/// Settings of the `collect` command live in this table of a profile.
+const COLLECT_TABLE: &str = "collect";
+
+/// Settings that are never printed in full.
+const SECRETS: [&str; 2] = ["private_key", "fee_private_key"];
+
+/// Arguments that select the profile rather than being part of it.
+const RESERVED: [&str; 4] = ["help", "version", "profile", "no_profile"];
+
+#[derive(Deserialize)]
+struct ConfigFile {
+    default_profile: Option<String>,
+    #[serde(default)]
+    profiles: BTreeMap<String, toml::Table>,
+}
+
+/// Where a resolved setting came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    Cli,
+    Env,
+    Profile,
+    Default,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let source = match self {
+            Source::Cli => "cli",
+            Source::Env => "env",
+            Source::Profile => "profile",
+            Source::Default => "default",
+        };
+        f.write_str(source)
+    }
+}
+
+/// Settings from the selected profile and `BITZ_*` environment variables,
+/// applied as defaults of the command line arguments. The order of precedence,
+/// highest first, is: command line flags, environment variables, the profile,
+/// built-in defaults.
+#[derive(Default)]
+pub struct Settings {
+    pub path: Option<PathBuf>,
+    pub profile: Option<String>,
+    global: BTreeMap<String, (Vec<String>, Source)>,
+    collect: BTreeMap<String, (Vec<String>, Source)>,
+}
+
+impl Settings {
+    /// Loads the profile chosen with `--profile`, `BITZ_PROFILE` or the file's
+    /// `default_profile`, and the environment overrides.
+    pub fn load(
+        profile: Option<String>,
+        no_profile: bool,
+        command: &Command,
+    ) -> Result<Self, String> {
+        let mut settings = Settings::default();
+
+        // Profile
+        if !no_profile {
+            if let Some(path) = config_path() {
+                let contents = std::fs::read_to_string(&path)
+                    .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+                let file: ConfigFile = toml::from_str(&contents)
+                    .map_err(|err| format!("Failed to parse {}: {}", path.display(), err))?;
+                let name = profile
+                    .or_else(|| std::env::var("BITZ_PROFILE").ok())
+                    .or(file.default_profile);
+                if let Some(name) = name {
+                    let profile = file.profiles.get(&name).ok_or_else(|| {
+                        format!("Profile `{}` not found in {}", name, path.display())
+                    })?;
+                    for (key, value) in profile {
+                        if key == COLLECT_TABLE {
+                            let table = value.as_table().ok_or_else(|| {
+                                format!("`{}` in profile `{}` must be a table", key, name)
+                            })?;
+                            for (key, value) in table {
+                                settings.collect.insert(
+                                    normalize(key),
+                                    (to_values(key, value)?, Source::Profile),
+                                );
+                            }
+                        } else {
+                            settings
+                                .global
+                                .insert(normalize(key), (to_values(key, value)?, Source::Profile));
+                        }
+                    }
+                    settings.profile = Some(name);
+                }
+                settings.path = Some(path);
+            } else if let Some(name) = profile {
+                return Err(format!(
+                    "Profile `{}` given but no {} was found",
+                    name, CONFIG_FILE
+                ));
+            }
+        }
+
+        // Environment overrides the profile
+        for arg in command.get_arguments() {
+            let id = arg.get_id().as_str();
+            if RESERVED.contains(&id) {
+                continue;
+            }
+            if let Ok(value) = std::env::var(env_var("BITZ_", id)) {
+                settings
+                    .global
+                    .insert(id.to_string(), (split(&value, arg), Source::Env));
+            }
+        }
+        if let Some(collect) = command.find_subcommand(COLLECT_TABLE) {
+            for arg in collect.get_arguments() {
+                let id = arg.get_id().as_str();
+                if let Ok(value) = std::env::var(env_var("BITZ_COLLECT_", id)) {
+                    settings
+                        .collect
+                        .insert(id.to_string(), (split(&value, arg), Source::Env));
+                }
+            }
+        }
+        Ok(settings)
+    }
+
+    /// Sets the resolved settings as argument defaults, so flags given on the
+    /// command line still take precedence.
+    pub fn apply(&self, mut command: Command) -> Result<Command, String> {
+        for (id, (values, _)) in &self.global {
+            check_known(&command, id)?;
+            let values = leak(values);
+            command = command.mut_arg(id, |arg| arg.default_values(values));
+        }
+        if !self.collect.is_empty() {
+            let collect = command
+                .find_subcommand(COLLECT_TABLE)
+                .cloned()
+                .ok_or_else(|| "The collect command is not available".to_string())?;
+            for id in self.collect.keys() {
+                check_known(&collect, id).map_err(|err| format!("{} in [collect]", err))?;
+            }
+            command = command.mut_subcommand(COLLECT_TABLE, |mut collect| {
+                for (id, (values, _)) in &self.collect {
+                    let values = leak(values);
+                    collect = collect.mut_arg(id, |arg| arg.default_values(values));
+                }
+                collect
+            });
+        }
+        Ok(command)
+    }
+
+    /// Prints every global and `collect` setting with the value it resolved to
+    /// and where it came from.
+    pub fn show(&self, command: &Command, matches: &ArgMatches) {
+        let mut data = vec![];
+        data.push(TableData {
+            key: "Config file".to_string(),
+            value: match &self.path {
+                Some(path) => path.display().to_string(),
+                None => "none".to_string(),
+            },
+        });
+        data.push(TableData {
+            key: "Profile".to_string(),
+            value: self.profile.clone().unwrap_or("none".to_string()),
+        });
+
+        // Global settings, which may also be given on the command line
+        let len1 = data.len();
+        for arg in command.get_arguments() {
+            let id = arg.get_id().as_str();
+            if RESERVED.contains(&id) {
+                continue;
+            }
+            let values: Vec<String> = matches
+                .get_raw(id)
+                .map(|raw| raw.map(|v| v.to_string_lossy().to_string()).collect())
+                .unwrap_or_default();
+            let source = match matches.value_source(id) {
+                Some(ValueSource::CommandLine) => Source::Cli,
+                _ => self
+                    .global
+                    .get(id)
+                    .map_or(Source::Default, |(_, source)| *source),
+            };
+            data.push(setting_row(id, &values, source));
+        }
+
+        // Collect settings
+        let len2 = data.len();
+        if let Some(collect) = command.find_subcommand(COLLECT_TABLE) {
+            for arg in collect.get_arguments() {
+                let id = arg.get_id().as_str();
+                if RESERVED.contains(&id) || arg.is_global_set() {
+                    continue;
+                }
+                let (values, source) = match self.collect.get(id) {
+                    Some((values, source)) => (values.clone(), *source),
+                    None => (
+                        arg.get_default_values()
+                            .iter()
+                            .map(|v| v.to_string_lossy().to_string())
+                            .collect(),
+                        Source::Default,
+                    ),
+                };
+                data.push(setting_row(id, &values, source));
+            }
+        }
+
+        let mut table = Table::new(data);
+        table.with(Remove::row(Rows::first()));
+        table.with(Style::blank());
+        table.section_title(0, "Config");
+        table.section_title(len1, "Global");
+        table.section_title(len2, "Collect");
+        println!("{table}\n");
+        println!(
+            "Precedence: {} > {} > {} > {}\n",
+            "cli".bold(),
+            "env (BITZ_*)".bold(),
+            "profile".bold(),
+            "default".bold()
+        );
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("BITZ_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let local = Path::new(CONFIG_FILE);
+    if local.exists() {
+        return Some(local.to_path_buf());
+    }
+    let global = config_dir()?.join(CONFIG_FILE);
+    global.exists().then_some(global)
+}
+
+/// `~/.config/bitz/`, home of the global config file and of state shared by
+/// processes started from any working directory.
+pub fn config_dir() -> Option<PathBuf> {
+    let home = std::env::var("HOME").ok()?;
+    Some(Path::new(&home).join(".config").join("bitz"))
+}
+
+/// Profile keys may use the flag spelling (`priority-fee`) or the field name.
+fn normalize(key: &str) -> String {
+    key.replace('-', "_")
+}
+
+fn env_var(prefix: &str, id: &str) -> String {
+    format!("{}{}", prefix, id.to_uppercase())
+}
+
+fn to_values(key: &str, value: &toml::Value) -> Result<Vec<String>, String> {
+    match value {
+        toml::Value::String(s) => Ok(vec![s.clone()]),
+        toml::Value::Integer(i) => Ok(vec![i.to_string()]),
+        toml::Value::Float(f) => Ok(vec![f.to_string()]),
+        toml::Value::Boolean(b) => Ok(vec![b.to_string()]),
+        toml::Value::Array(values) => values
+            .iter()
+            .map(|value| match value {
+                toml::Value::Array(_) | toml::Value::Table(_) => {
+                    Err(format!("`{}` must be a list of plain values", key))
+                }
+                value => Ok(to_values(key, value)?.remove(0)),
+            })
+            .collect(),
+        _ => Err(format!("`{}` must be a plain value or a list", key)),
+    }
+}
+
+fn split(value: &str, arg: &clap::Arg) -> Vec<String> {
+    match arg.get_value_delimiter() {
+        Some(delimiter) => value.split(delimiter).map(String::from).collect(),
+        None => vec![value.to_string()],
+    }
+}
+
+fn check_known(command: &Command, id: &str) -> Result<(), String> {
+    if RESERVED.contains(&id) || !command.get_arguments().any(|arg| arg.get_id() == id) {
+        return Err(format!("Unknown setting `{}`", id));
+    }
+    Ok(())
+}
+
+/// Argument defaults must outlive the command, which lives for the whole run.
+fn leak(values: &[String]) -> Vec<&'static str> {
+    values
+        .iter()
+        .map(|value| &*Box::leak(value.clone().into_boxed_str()))
+        .collect()
+}
+
+fn setting_row(id: &str, values: &[String], source: Source) -> TableData {
+    let value = if values.is_empty() {
+        "–".dimmed().to_string()
+    } else if SECRETS.contains(&id) {
+        "********".to_string()
+    } else {
+        values.join(", ")
+    };
+    TableData {
+        key: id.replace('_', "-"),
+        value: format!("{} ({})", value, source),
+    }
+}
@@ -1,12 +1,21 @@
+use std::time::Duration;
+
+use eore_api::error::OreError;
+use solana_client::client_error::ClientError;
+use solana_program::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("{0}")]
     Internal(String),
+    #[error("{0}")]
+    Anyhow(#[from] anyhow::Error),
     #[error("reqwest")]
     Reqwest(#[from] reqwest::Error),
     #[error("solana parse pubkey")]
     SolanaParsePubkey(#[from] solana_sdk::pubkey::ParsePubkeyError),
-    #[error("solana rpc client")]
+    #[error("solana rpc client: {0}")]
     SolanaRpcClient(#[from] solana_client::client_error::ClientError),
     #[error("solana program")]
     SolanaProgram(#[from] solana_program::program_error::ProgramError),
@@ -14,4 +23,163 @@ pub enum Error {
     ParseInt(#[from] std::num::ParseIntError),
     #[error("number of devices per keypair exceeded")]
     TooManyDevices,
+    #[error("transaction not confirmed after {0} attempts")]
+    SendAttemptsExhausted(usize),
+    #[error("transaction not confirmed within {0}s")]
+    SendDeadlineExceeded(u64),
+    #[error("fee payer balance {0} ETH is below the minimum")]
+    LowBalance(f64),
+    #[error("daily fee budget exhausted, {0} ETH spent in the last 24h")]
+    FeeBudgetExhausted(f64),
+}
+
+/// Failure classes for the collecting loop. Each class has its own retry policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// RPC request failed, timed out or was rate limited.
+    Transient,
+    /// The transaction blockhash expired before it landed.
+    BlockhashExpired,
+    /// The epoch has ended and the reset instruction must be included.
+    NeedsReset,
+    /// The solution no longer satisfies the current proof challenge.
+    StaleChallenge,
+    /// The signer or fee payer cannot pay for the transaction.
+    InsufficientFunds,
+    /// The fee payer has spent its daily fee budget.
+    BudgetExhausted,
+    /// Misconfiguration that retrying will not fix.
+    Fatal,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Exponential backoff for the given retry attempt, capped at `max_backoff`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+}
+
+impl ErrorClass {
+    pub fn policy(&self) -> RetryPolicy {
+        match self {
+            ErrorClass::Transient => RetryPolicy {
+                max_retries: 8,
+                initial_backoff: Duration::from_secs(1),
+                max_backoff: Duration::from_secs(30),
+            },
+            ErrorClass::BlockhashExpired => RetryPolicy {
+                max_retries: 3,
+                initial_backoff: Duration::from_millis(200),
+                max_backoff: Duration::from_secs(1),
+            },
+            ErrorClass::NeedsReset => RetryPolicy {
+                max_retries: 3,
+                initial_backoff: Duration::from_secs(1),
+                max_backoff: Duration::from_secs(5),
+            },
+            ErrorClass::StaleChallenge => RetryPolicy {
+                max_retries: u32::MAX,
+                initial_backoff: Duration::from_secs(1),
+                max_backoff: Duration::from_secs(1),
+            },
+            ErrorClass::InsufficientFunds => RetryPolicy {
+                max_retries: u32::MAX,
+                initial_backoff: Duration::from_secs(30),
+                max_backoff: Duration::from_secs(300),
+            },
+            ErrorClass::BudgetExhausted => RetryPolicy {
+                max_retries: u32::MAX,
+                initial_backoff: Duration::from_secs(60),
+                max_backoff: Duration::from_secs(900),
+            },
+            ErrorClass::Fatal => RetryPolicy {
+                max_retries: 0,
+                initial_backoff: Duration::ZERO,
+                max_backoff: Duration::ZERO,
+            },
+        }
+    }
+
+    pub fn classify_client_error(err: &ClientError) -> Self {
+        match err.get_transaction_error() {
+            Some(err) => Self::classify_transaction_error(&err),
+            None => {
+                if err.to_string().to_lowercase().contains("blockhash") {
+                    ErrorClass::BlockhashExpired
+                } else {
+                    ErrorClass::Transient
+                }
+            }
+        }
+    }
+
+    /// Unknown errors are `Transient`, so a single bad round never stops the
+    /// collecting loop. Only errors that point at a wrong signer, program or
+    /// account are `Fatal`.
+    pub fn classify_transaction_error(err: &TransactionError) -> Self {
+        match err {
+            TransactionError::BlockhashNotFound => ErrorClass::BlockhashExpired,
+            TransactionError::AccountNotFound
+            | TransactionError::InsufficientFundsForFee
+            | TransactionError::InsufficientFundsForRent { .. } => ErrorClass::InsufficientFunds,
+            TransactionError::SignatureFailure
+            | TransactionError::MissingSignatureForFee
+            | TransactionError::InvalidAccountForFee
+            | TransactionError::ProgramAccountNotFound
+            | TransactionError::InvalidProgramForExecution => ErrorClass::Fatal,
+            // Custom codes are read as BITZ errors. Besides the compute budget,
+            // which has none, every instruction of a mine transaction belongs
+            // to the BITZ program.
+            TransactionError::InstructionError(_, err) => match err {
+                InstructionError::Custom(code) if *code == OreError::NeedsReset as u32 => {
+                    ErrorClass::NeedsReset
+                }
+                InstructionError::Custom(code)
+                    if [OreError::HashInvalid, OreError::HashTooEasy, OreError::Spam]
+                        .iter()
+                        .any(|err| *err as u32 == *code) =>
+                {
+                    ErrorClass::StaleChallenge
+                }
+                InstructionError::Custom(code) if *code == OreError::AuthFailed as u32 => {
+                    ErrorClass::Fatal
+                }
+                InstructionError::InsufficientFunds => ErrorClass::InsufficientFunds,
+                InstructionError::MissingRequiredSignature
+                | InstructionError::IncorrectProgramId
+                | InstructionError::InvalidAccountOwner
+                | InstructionError::IllegalOwner => ErrorClass::Fatal,
+                _ => ErrorClass::Transient,
+            },
+            _ => ErrorClass::Transient,
+        }
+    }
+}
+
+impl Error {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            Error::SolanaRpcClient(err) => ErrorClass::classify_client_error(err),
+            Error::Internal(_)
+            | Error::Anyhow(_)
+            | Error::SolanaProgram(_)
+            | Error::Reqwest(_)
+            | Error::SendAttemptsExhausted(_)
+            | Error::SendDeadlineExceeded(_) => ErrorClass::Transient,
+            Error::LowBalance(_) => ErrorClass::InsufficientFunds,
+            Error::FeeBudgetExhausted(_) => ErrorClass::BudgetExhausted,
+            Error::SolanaParsePubkey(_) | Error::ParseInt(_) | Error::TooManyDevices => {
+                ErrorClass::Fatal
+            }
+        }
+    }
 }
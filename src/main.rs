@@ -1,17 +1,24 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client::rpc_client::RpcClientConfig;
 use solana_sdk::{
     commitment_config::CommitmentConfig,
     signature::{read_keypair_file, Keypair, Signer},
 };
-use std::sync::Arc;
+use std::{str::FromStr, sync::Arc, time::Duration};
 
 use args::*;
+use config::Settings;
 use error::Error;
-use utils::{PoolCollectingData, SoloCollectingData};
+use send::{FeeConfig, FeeLedger, FeeStrategy, SendPolicy, SpendLimits};
+use utils::{
+    websocket_url, FailoverSender, OutputFormat, PoolCollectingData, RpcEndpoint,
+    SoloCollectingData,
+};
 
 mod args;
 mod command;
+mod config;
 mod error;
 mod send;
 mod utils;
@@ -29,12 +36,22 @@ struct Miner {
     pub priority_fee: Option<u64>,
     pub dynamic_fee_url: Option<String>,
     pub dynamic_fee: bool,
+    pub fee_config: FeeConfig,
+    pub fee_ema: Arc<std::sync::Mutex<Option<f64>>>,
+    pub compute_unit_margin: u64,
+    pub simulate: bool,
+    pub send_policy: SendPolicy,
+    pub spend_limits: SpendLimits,
+    pub fee_ledger: FeeLedger,
     pub rpc_client: Arc<RpcClient>,
     pub fee_payer_filepath: Option<String>,
     pub fee_private_key: Option<String>,
     pub solo_collecting_data: Arc<std::sync::RwLock<Vec<SoloCollectingData>>>,
     pub pool_collecting_data: Arc<std::sync::RwLock<Vec<PoolCollectingData>>>,
     pub sub_private_filepath: Option<String>,
+    pub rpc_endpoints: Vec<String>,
+    pub ws_url: Option<String>,
+    pub output: OutputFormat,
 }
 
 #[derive(Subcommand, Debug)]
@@ -75,6 +92,21 @@ enum Commands {
     
     #[command(about = "批量查询BITZ余额和挖矿时间")]
     Check(CheckArgs),
+
+    #[command(about = "批量为子钱包补充ETH gas")]
+    Fund(FundArgs),
+
+    #[command(about = "Inspect dynamic priority fee estimates")]
+    Fees(FeesArgs),
+
+    #[command(about = "Estimate rewards and fees for a hashpower")]
+    Estimate(EstimateArgs),
+
+    #[command(about = "Fetch the onchain mining history of an account")]
+    History(HistoryArgs),
+
+    #[command(about = "Inspect the bitz.toml profile settings")]
+    Config(ConfigArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -83,10 +115,11 @@ struct Args {
     #[arg(
         long,
         value_name = "NETWORK_URL",
-        help = "Network address of your RPC provider",
+        help = "Network address of your RPC provider. Repeat or comma-separate for multiple endpoints, optionally prefixed with roles, e.g. read=URL, send=URL, fee=URL or read+send=URL.",
+        value_delimiter = ',',
         global = true
     )]
-    rpc: Option<String>,
+    rpc: Vec<String>,
 
     #[clap(
         global = true,
@@ -149,6 +182,144 @@ struct Args {
     #[arg(long, help = "Enable dynamic priority fees", global = true)]
     dynamic_fee: bool,
 
+    #[arg(
+        long,
+        value_name = "STRATEGY",
+        help = "Where dynamic priority fee estimates come from.",
+        default_value = "local",
+        global = true
+    )]
+    fee_strategy: FeeStrategy,
+
+    #[arg(
+        long,
+        value_name = "PERCENTILE",
+        help = "Percentile of recent priority fees to target with dynamic fees.",
+        default_value = "75",
+        value_parser = clap::value_parser!(u8).range(0..=100),
+        global = true
+    )]
+    fee_percentile: u8,
+
+    #[arg(
+        long,
+        value_name = "ALPHA",
+        help = "Weight of the newest estimate in the moving average of dynamic fees. 1 disables smoothing.",
+        default_value = "0.3",
+        global = true
+    )]
+    fee_smoothing: f64,
+
+    #[arg(
+        long,
+        value_name = "MICROLAMPORTS",
+        help = "Minimum price to pay for compute units with dynamic fees.",
+        default_value = "0",
+        global = true
+    )]
+    fee_floor: u64,
+
+    #[arg(
+        long,
+        value_name = "FACTOR",
+        help = "Factor the priority fee is raised by on each unconfirmed rebroadcast, up to --priority-fee.",
+        default_value = "1.2",
+        global = true
+    )]
+    fee_escalation: f64,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Margin added on top of simulated compute units for dynamic compute budgets.",
+        default_value = "10",
+        global = true
+    )]
+    cu_margin: u64,
+
+    #[arg(
+        long,
+        help = "Build, sign and simulate transactions without broadcasting them.",
+        global = true
+    )]
+    simulate: bool,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "Maximum number of times a transaction is broadcast before giving up.",
+        default_value = "150",
+        global = true
+    )]
+    send_attempts: usize,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Maximum time to keep rebroadcasting a transaction before giving up.",
+        default_value = "120",
+        global = true
+    )]
+    send_timeout: u64,
+
+    #[arg(
+        long,
+        value_name = "MILLISECONDS",
+        help = "Initial delay between rebroadcasts, doubled on every attempt up to 2s.",
+        default_value = "200",
+        global = true
+    )]
+    send_backoff: u64,
+
+    #[arg(
+        long,
+        value_name = "ETH",
+        help = "Maximum fees the fee payer may spend over any 24 hours.",
+        global = true
+    )]
+    max_fee_per_day: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "ETH",
+        help = "Pause sending and alert while the fee payer balance is below this amount.",
+        default_value = "0",
+        global = true
+    )]
+    min_balance: f64,
+
+    #[arg(
+        long,
+        value_name = "ETH",
+        help = "Price of one BITZ in ETH. Solutions whose reward is worth less than the fee are not submitted.",
+        global = true
+    )]
+    bitz_price: Option<f64>,
+
+    #[arg(
+        long,
+        help = "Count only the signer's fees against --max-fee-per-day. Set by batch mining for its child processes.",
+        hide = true,
+        global = true
+    )]
+    fee_budget_per_signer: bool,
+
+    #[arg(
+        long,
+        value_name = "WEBSOCKET_URL",
+        help = "Websocket URL for account and signature subscriptions. Defaults to the first read RPC endpoint.",
+        global = true
+    )]
+    ws_url: Option<String>,
+
+    #[arg(
+        long,
+        help = "Disable websocket subscriptions and poll the RPC instead.",
+        global = true,
+        conflicts_with = "ws_url"
+    )]
+    no_ws: bool,
+
     #[arg(
         long,
         value_name = "SUB_PRIVATE_FILEPATH",
@@ -157,6 +328,34 @@ struct Args {
     )]
     sub_private: Option<String>,
 
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Output format of query commands. json prints raw amounts and addresses for scripts.",
+        default_value = "table",
+        global = true
+    )]
+    output: OutputFormat,
+
+    // Read before parsing to select the profile, see `Settings::load`
+    #[allow(dead_code)]
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Profile of bitz.toml to load settings from. Defaults to BITZ_PROFILE or the file's default_profile.",
+        global = true
+    )]
+    profile: Option<String>,
+
+    #[allow(dead_code)]
+    #[arg(
+        long,
+        help = "Ignore bitz.toml and load settings from the command line and environment only.",
+        global = true,
+        conflicts_with = "profile"
+    )]
+    no_profile: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -166,7 +365,33 @@ async fn main() {
     // Initialize logger
     env_logger::init_from_env(env_logger::Env::default().default_filter_or("info"));
     
-    let args = Args::parse();
+    // Resolve settings from bitz.toml and the environment, then the command line
+    let raw_args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    let selection = Args::command()
+        .ignore_errors(true)
+        .get_matches_from(&raw_args);
+    let settings = Settings::load(
+        selection.get_one::<String>("profile").cloned(),
+        selection.get_one::<bool>("no_profile").copied().unwrap_or(false),
+        &Args::command(),
+    )
+    .unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    });
+    let command = settings.apply(Args::command()).unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    });
+    let matches = command.clone().get_matches_from(&raw_args);
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    if let Commands::Config(ConfigArgs {
+        command: ConfigCommand::Show(_),
+    }) = &args.command
+    {
+        settings.show(&command, &matches);
+        return;
+    }
 
     // Load the config file from custom path, the default path, or use default config values
     let cli_config = if let Some(config_file) = &args.config_file {
@@ -184,17 +409,55 @@ async fn main() {
     let default_rpc_url = String::from("https://eclipse.helius-rpc.com/");
     
     // Initialize miner.
-    let cluster = args.rpc.unwrap_or_else(|| {
+    let rpc_endpoints = if args.rpc.is_empty() {
         if cli_config.json_rpc_url.is_empty() {
-            default_rpc_url
+            vec![default_rpc_url]
         } else {
-            cli_config.json_rpc_url
+            vec![cli_config.json_rpc_url]
         }
+    } else {
+        args.rpc
+    };
+    let endpoints = rpc_endpoints
+        .iter()
+        .map(|spec| RpcEndpoint::from_str(spec))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        });
+    if !endpoints.iter().any(|endpoint| endpoint.roles.read) {
+        eprintln!("error: At least one RPC endpoint must have the `read` role");
+        std::process::exit(1);
+    }
+    let dynamic_fee_url = args.dynamic_fee_url.or_else(|| {
+        endpoints
+            .iter()
+            .find(|endpoint| endpoint.roles.fee)
+            .map(|endpoint| endpoint.url.clone())
     });
     
+    let ws_url = if args.no_ws {
+        None
+    } else {
+        args.ws_url.or_else(|| {
+            endpoints
+                .iter()
+                .find(|endpoint| endpoint.roles.read)
+                .map(|endpoint| websocket_url(&endpoint.url))
+        })
+    };
+    
     let default_keypair = args.keypair.unwrap_or(cli_config.keypair_path.clone());
     let fee_payer_filepath = args.fee_payer.unwrap_or(default_keypair.clone());
-    let rpc_client = RpcClient::new_with_commitment(cluster, CommitmentConfig::confirmed());
+    let rpc_client = if endpoints.len() == 1 {
+        RpcClient::new_with_commitment(endpoints[0].url.clone(), CommitmentConfig::confirmed())
+    } else {
+        RpcClient::new_sender(
+            FailoverSender::new(endpoints),
+            RpcClientConfig::with_commitment(CommitmentConfig::confirmed()),
+        )
+    };
 
     let solo_collecting_data = Arc::new(std::sync::RwLock::new(Vec::new()));
     let pool_collecting_data = Arc::new(std::sync::RwLock::new(Vec::new()));
@@ -204,13 +467,36 @@ async fn main() {
         args.priority_fee,
         Some(default_keypair),
         args.private_key,
-        args.dynamic_fee_url,
+        dynamic_fee_url,
         args.dynamic_fee,
+        FeeConfig {
+            strategy: args.fee_strategy,
+            percentile: args.fee_percentile,
+            smoothing: args.fee_smoothing.clamp(0.0, 1.0),
+            floor: args.fee_floor,
+            escalation: args.fee_escalation.max(1.0),
+        },
+        args.cu_margin,
+        args.simulate,
+        SendPolicy {
+            max_attempts: args.send_attempts,
+            deadline: Duration::from_secs(args.send_timeout),
+            backoff: Duration::from_millis(args.send_backoff),
+        },
+        SpendLimits {
+            max_fee_per_day: args.max_fee_per_day,
+            min_balance: args.min_balance,
+            bitz_price: args.bitz_price,
+            budget_per_signer: args.fee_budget_per_signer,
+        },
         Some(fee_payer_filepath),
         args.fee_private_key,
         solo_collecting_data,
         pool_collecting_data,
         args.sub_private,
+        rpc_endpoints,
+        ws_url,
+        args.output,
     ));
 
     // Execute user command.
@@ -264,6 +550,23 @@ async fn main() {
         Commands::Check(args) => {
             miner.check(args).await;
         }
+        Commands::Fund(args) => {
+            if let Err(err) = miner.fund(args).await {
+                println!("{:?}", err);
+            }
+        }
+        Commands::Fees(args) => {
+            miner.fees(args);
+        }
+        Commands::Estimate(args) => {
+            miner.estimate(args).await;
+        }
+        Commands::History(args) => {
+            if let Err(err) = miner.history(args).await {
+                println!("{:?}", err);
+            }
+        }
+        Commands::Config(_) => unreachable!("handled before the miner is built"),
     }
 }
 
@@ -275,11 +578,19 @@ impl Miner {
         private_key: Option<String>,
         dynamic_fee_url: Option<String>,
         dynamic_fee: bool,
+        fee_config: FeeConfig,
+        compute_unit_margin: u64,
+        simulate: bool,
+        send_policy: SendPolicy,
+        spend_limits: SpendLimits,
         fee_payer_filepath: Option<String>,
         fee_private_key: Option<String>,
         solo_collecting_data: Arc<std::sync::RwLock<Vec<SoloCollectingData>>>,
         pool_collecting_data: Arc<std::sync::RwLock<Vec<PoolCollectingData>>>,
         sub_private_filepath: Option<String>,
+        rpc_endpoints: Vec<String>,
+        ws_url: Option<String>,
+        output: OutputFormat,
     ) -> Self {
         Self {
             rpc_client,
@@ -288,11 +599,21 @@ impl Miner {
             priority_fee,
             dynamic_fee_url,
             dynamic_fee,
+            fee_config,
+            fee_ema: Arc::new(std::sync::Mutex::new(None)),
+            compute_unit_margin,
+            simulate,
+            send_policy,
+            spend_limits,
+            fee_ledger: FeeLedger::default(),
             fee_payer_filepath,
             fee_private_key,
             solo_collecting_data,
             pool_collecting_data,
             sub_private_filepath,
+            rpc_endpoints,
+            ws_url,
+            output,
         }
     }
 
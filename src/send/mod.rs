@@ -1,2 +1,10 @@
 mod send_and_confirm;
-mod priority_fee;
\ No newline at end of file
+
+pub use priority_fee::{
+    read_fee_history, read_landed_history, FeeConfig, FeeStrategy, LandedRecord,
+};
+pub use send_and_confirm::SendPolicy;
+pub use spending::{transaction_fee, FeeLedger, SpendLimits};
+mod priority_fee;
+mod simulate;
+mod spending;
@@ -1,239 +1,372 @@
-use crate::Miner;
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
+use async_trait::async_trait;
 use eore_api::consts::BUS_ADDRESSES;
+use log::{debug, warn};
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_response::RpcPrioritizationFee};
 use solana_sdk::pubkey::Pubkey;
-use std::{collections::HashMap, str::FromStr};
 
-use solana_client::rpc_response::RpcPrioritizationFee;
+use crate::{
+    config::config_dir,
+    utils::{append_jsonl, read_jsonl},
+    Miner,
+};
+
+/// Number of most recent slots the percentile is taken over.
+const RECENT_SLOTS: usize = 150;
+
+/// Escalate twice as fast once the landing deadline is this close.
+const URGENT_WINDOW: Duration = Duration::from_secs(10);
 
-use url::Url;
-enum FeeStrategy {
+const FEE_HISTORY_PATH: &str = "logs/fee_history.jsonl";
+const LANDED_HISTORY_PATH: &str = "logs/landed.jsonl";
+
+/// Where priority fee estimates come from. Chosen with `--fee-strategy`.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeeStrategy {
+    /// Standard `getRecentPrioritizationFees` on the fee RPC.
+    #[default]
+    Local,
+    /// Helius `getPriorityFeeEstimate`.
     Helius,
+    /// Triton `getRecentPrioritizationFees` with a per-slot percentile.
     Triton,
-    LOCAL,
+    /// Alchemy `getRecentPrioritizationFees`.
     Alchemy,
-    Quiknode,
+    /// QuickNode `qn_estimatePriorityFees` add-on.
+    Quicknode,
 }
 
-impl Miner {
-    pub async fn get_dynamic_priority_fee(&self) -> Result<u64, String> {
-        // Get url
-        let rpc_url = self
-            .dynamic_fee_url
-            .clone()
-            .unwrap_or(self.rpc_client.url());
+/// Tuning for dynamic priority fees. The ceiling is `--priority-fee`.
+#[derive(Clone, Copy, Debug)]
+pub struct FeeConfig {
+    pub strategy: FeeStrategy,
+    pub percentile: u8,
+    pub smoothing: f64,
+    pub floor: u64,
+    pub escalation: f64,
+}
 
-        // Select fee estiamte strategy
-        let host = Url::parse(&rpc_url)
-            .unwrap()
-            .host_str()
-            .unwrap()
-            .to_string();
-        let strategy = if host.contains("helius-rpc.com") {
-            FeeStrategy::Helius
-        } else if host.contains("alchemy.com") {
-            FeeStrategy::Alchemy
-        } else if host.contains("quiknode.pro") {
-            FeeStrategy::Quiknode
-        } else if host.contains("rpcpool.com") {
-            FeeStrategy::Triton
-        } else {
-            FeeStrategy::LOCAL
+impl FeeConfig {
+    /// Price for the next rebroadcast of a transaction that has not landed yet.
+    /// The fee is multiplied by the escalation factor, squared within
+    /// `URGENT_WINDOW` of the landing deadline, and goes straight to the cap
+    /// once the deadline has passed.
+    pub fn escalate(&self, fee: u64, cap: u64, remaining: Option<Duration>) -> u64 {
+        let factor = match remaining {
+            Some(remaining) if remaining.is_zero() => return cap.max(fee),
+            Some(remaining) if remaining < URGENT_WINDOW => self.escalation * self.escalation,
+            _ => self.escalation,
         };
+        let escalated = (fee.max(self.floor).max(1) as f64 * factor).ceil() as u64;
+        escalated.min(cap).max(fee)
+    }
+}
 
-        // Build fee estimate request
-        let client = Client::new();
-        let ore_addresses: Vec<String> = std::iter::once(eore_api::ID.to_string())
-            .chain(BUS_ADDRESSES.iter().map(|pubkey| pubkey.to_string()))
-            .collect();
-        let body = match strategy {
-            FeeStrategy::Helius => Some(json!({
-                "jsonrpc": "2.0",
-                "id": "priority-fee-estimate",
-                "method": "getPriorityFeeEstimate",
-                "params": [{
-                    "accountKeys": ore_addresses,
-                    "options": {
-                        "recommended": true
-                    }
-                }]
-            })),
-            FeeStrategy::Alchemy => Some(json!({
-                "jsonrpc": "2.0",
-                "id": "priority-fee-estimate",
-                "method": "getRecentPrioritizationFees",
-                "params": [
-                    ore_addresses
-                ]
-            })),
-            FeeStrategy::Quiknode => Some(json!({
-                "jsonrpc": "2.0",
-                "id": "1",
-                "method": "qn_estimatePriorityFees",
-                "params": {
-                    "account": "EorefDWqzJK31vLxaqkDGsx3CRKqPVpWfuJL7qBQMZYd",
-                    "last_n_blocks": 100
-                }
-            })),
-            FeeStrategy::Triton => Some(json!({
-                "jsonrpc": "2.0",
-                "id": "priority-fee-estimate",
-                "method": "getRecentPrioritizationFees",
-                "params": [
-                    ore_addresses,
-                    {
-                        "percentile": 5000,
-                    }
-                ]
-            })),
-            FeeStrategy::LOCAL => None,
-        };
+#[async_trait]
+pub trait FeeEstimator: Send + Sync {
+    /// Estimates the priority fee in microlamports per compute unit at the given
+    /// percentile of recent fees paid for writes to the accounts.
+    async fn estimate(&self, accounts: &[Pubkey], percentile: u8) -> Result<u64, String>;
+}
 
-        // Send rpc request
-        let response = if let Some(body) = body {
-            let response: Value = client
-                .post(rpc_url)
-                .json(&body)
-                .send()
-                .await
-                .unwrap()
-                .json()
-                .await
-                .unwrap();
-            response
-        } else {
-            Value::Null
-        };
+/// Estimator for RPCs that only implement the standard
+/// `getRecentPrioritizationFees` method.
+pub struct RecentFeesEstimator {
+    rpc_client: Arc<RpcClient>,
+}
 
-        // Parse response
-        let calculated_fee = match strategy {
-            FeeStrategy::Helius => response["result"]["priorityFeeEstimate"]
-                .as_f64()
-                .map(|fee| fee as u64)
-                .ok_or_else(|| format!("Failed to parse priority fee response: {:?}", response)),
-            FeeStrategy::Quiknode => response["result"]["per_compute_unit"]["medium"]
-                .as_f64()
-                .map(|fee| fee as u64)
-                .ok_or_else(|| format!("Please enable the Solana Priority Fee API add-on in your QuickNode account.")),
-            FeeStrategy::Alchemy => response["result"]
-                .as_array()
-                .and_then(|arr| {
-                    Some(
-                        arr.into_iter()
-                            .map(|v| v["prioritizationFee"].as_u64().unwrap())
-                            .collect::<Vec<u64>>(),
-                    )
-                })
-                .and_then(|fees| {
-                    Some(
-                        ((fees.iter().sum::<u64>() as f32 / fees.len() as f32).ceil() * 1.2) as u64,
-                    )
-                })
-                .ok_or_else(|| format!("Failed to parse priority fee response: {:?}", response)),
-            FeeStrategy::Triton => {
-                serde_json::from_value::<Vec<RpcPrioritizationFee>>(response["result"].clone())
-                    .map(|prioritization_fees| {
-                        estimate_prioritization_fee_microlamports(prioritization_fees)
-                    })
-                    .or_else(|error: serde_json::Error| {
-                        Err(format!(
-                            "Failed to parse priority fee response: {response:?}, error: {error}"
-                        ))
-                    })
-            },
-            FeeStrategy::LOCAL => {
-                self.local_dynamic_fee().await.or_else(|err| {
-                    Err(format!("Failed to parse priority fee response: {err}"))
-                })
-            },
+#[async_trait]
+impl FeeEstimator for RecentFeesEstimator {
+    async fn estimate(&self, accounts: &[Pubkey], percentile: u8) -> Result<u64, String> {
+        let fees = self
+            .rpc_client
+            .get_recent_prioritization_fees(accounts)
+            .await
+            .map_err(|err| format!("Failed to fetch recent prioritization fees: {}", err))?;
+        percentile_of_recent_slots(fees, percentile)
+    }
+}
+
+pub struct HeliusEstimator {
+    client: Client,
+    url: String,
+}
+
+#[async_trait]
+impl FeeEstimator for HeliusEstimator {
+    async fn estimate(&self, accounts: &[Pubkey], percentile: u8) -> Result<u64, String> {
+        let level = match percentile {
+            0..=12 => "Min",
+            13..=37 => "Low",
+            38..=62 => "Medium",
+            63..=85 => "High",
+            86..=99 => "VeryHigh",
+            _ => "UnsafeMax",
         };
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": "priority-fee-estimate",
+            "method": "getPriorityFeeEstimate",
+            "params": [{
+                "accountKeys": accounts.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                "options": {
+                    "priorityLevel": level
+                }
+            }]
+        });
+        let response = post(&self.client, &self.url, &body).await?;
+        response["result"]["priorityFeeEstimate"]
+            .as_f64()
+            .map(|fee| fee as u64)
+            .ok_or_else(|| format!("Failed to parse priority fee response: {:?}", response))
+    }
+}
+
+pub struct TritonEstimator {
+    client: Client,
+    url: String,
+}
 
-        // Check if the calculated fee is higher than max
-        match calculated_fee {
-            Err(err) => Err(err),
-            Ok(fee) => {
-                if let Some(max_fee) = self.priority_fee {
-                    Ok(fee.min(max_fee))
-                } else {
-                    Ok(fee)
+#[async_trait]
+impl FeeEstimator for TritonEstimator {
+    async fn estimate(&self, accounts: &[Pubkey], percentile: u8) -> Result<u64, String> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": "priority-fee-estimate",
+            "method": "getRecentPrioritizationFees",
+            "params": [
+                accounts.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                {
+                    "percentile": percentile as u64 * 100,
                 }
+            ]
+        });
+        let response = post(&self.client, &self.url, &body).await?;
+        let fees = serde_json::from_value::<Vec<RpcPrioritizationFee>>(response["result"].clone())
+            .map_err(|err| {
+                format!(
+                    "Failed to parse priority fee response: {:?}, error: {}",
+                    response, err
+                )
+            })?;
+        percentile_of_recent_slots(fees, percentile)
+    }
+}
+
+pub struct QuicknodeEstimator {
+    client: Client,
+    url: String,
+}
+
+#[async_trait]
+impl FeeEstimator for QuicknodeEstimator {
+    async fn estimate(&self, _accounts: &[Pubkey], percentile: u8) -> Result<u64, String> {
+        let level = match percentile {
+            0..=37 => "low",
+            38..=62 => "medium",
+            63..=85 => "high",
+            _ => "extreme",
+        };
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "method": "qn_estimatePriorityFees",
+            "params": {
+                "account": eore_api::ID.to_string(),
+                "last_n_blocks": 100
             }
-        }
+        });
+        let response = post(&self.client, &self.url, &body).await?;
+        response["result"]["per_compute_unit"][level]
+            .as_f64()
+            .map(|fee| fee as u64)
+            .ok_or_else(|| {
+                "Please enable the Solana Priority Fee API add-on in your QuickNode account."
+                    .to_string()
+            })
     }
+}
 
-    pub async fn local_dynamic_fee(&self) -> Result<u64, Box<dyn std::error::Error>> {
-        let client = self.rpc_client.clone();
-        let pubkey = [
-            "EorefDWqzJK31vLxaqkDGsx3CRKqPVpWfuJL7qBQMZYd",
-            "5HngGmYzvSuh3XyU11brHDpMTHXQQRQQT4udGFtQSjgR",
-            "2oLNTQKRb4a2117kFi6BYTUDu3RPrMVAHFhCfPKMosxX",
-        ];
-        let address_strings = pubkey;
-
-        // Convert strings to Pubkey
-        let addresses: Vec<Pubkey> = address_strings
-            .into_iter()
-            .map(|addr_str| Pubkey::from_str(addr_str).expect("Invalid address"))
-            .collect();
+/// A recorded dynamic fee estimate, inspected with `bitz fees history`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FeeRecord {
+    pub timestamp: i64,
+    pub strategy: FeeStrategy,
+    pub percentile: u8,
+    pub sample: u64,
+    pub smoothed: u64,
+    pub fee: u64,
+}
+
+/// A transaction that landed, with the fee paid in lamports and the number of
+/// broadcasts it took.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LandedRecord {
+    pub timestamp: i64,
+    pub signature: String,
+    pub fee_payer: String,
+    /// Empty in records written before signers were recorded.
+    #[serde(default)]
+    pub signer: String,
+    pub cu_limit: u32,
+    pub cu_price: u64,
+    pub fee: u64,
+    pub attempts: usize,
+}
+
+/// Reads recorded fee estimates, oldest first.
+pub fn read_fee_history() -> Result<Vec<FeeRecord>, std::io::Error> {
+    read_jsonl(FEE_HISTORY_PATH)
+}
+
+/// Reads recorded landed transactions, oldest first.
+pub fn read_landed_history() -> Result<Vec<LandedRecord>, std::io::Error> {
+    read_jsonl(landed_history_path())
+}
 
-        // Get recent prioritization fees
-        let recent_prioritization_fees = client.get_recent_prioritization_fees(&addresses).await?;
-        if recent_prioritization_fees.is_empty() {
-            return Err("No recent prioritization fees".into());
+/// The landed history lives in the config directory, so the fee budget is read
+/// from the same file whatever directory a process is started from.
+fn landed_history_path() -> PathBuf {
+    match config_dir() {
+        Some(dir) => dir.join(LANDED_HISTORY_PATH),
+        None => PathBuf::from(LANDED_HISTORY_PATH),
+    }
+}
+
+fn record_fee(record: &FeeRecord) -> Result<(), std::io::Error> {
+    append_jsonl(FEE_HISTORY_PATH, record)
+}
+
+pub(crate) fn record_landed(record: &LandedRecord) -> Result<(), std::io::Error> {
+    append_jsonl(landed_history_path(), record)
+}
+
+impl Miner {
+    pub fn fee_estimator(&self) -> Box<dyn FeeEstimator> {
+        let url = self
+            .dynamic_fee_url
+            .clone()
+            .unwrap_or(self.rpc_client.url());
+        let client = Client::new();
+        match self.fee_config.strategy {
+            FeeStrategy::Local if self.dynamic_fee_url.is_none() => Box::new(RecentFeesEstimator {
+                rpc_client: self.rpc_client.clone(),
+            }),
+            FeeStrategy::Local | FeeStrategy::Alchemy => Box::new(RecentFeesEstimator {
+                rpc_client: Arc::new(RpcClient::new(url)),
+            }),
+            FeeStrategy::Helius => Box::new(HeliusEstimator { client, url }),
+            FeeStrategy::Triton => Box::new(TritonEstimator { client, url }),
+            FeeStrategy::Quicknode => Box::new(QuicknodeEstimator { client, url }),
         }
-        let mut sorted_fees: Vec<_> = recent_prioritization_fees.into_iter().collect();
-        sorted_fees.sort_by(|a, b| b.slot.cmp(&a.slot));
-        let chunk_size = 150;
-        let chunks: Vec<_> = sorted_fees.chunks(chunk_size).take(3).collect();
-        let mut percentiles: HashMap<u8, u64> = HashMap::new();
-        for (_, chunk) in chunks.iter().enumerate() {
-            let fees: Vec<u64> = chunk.iter().map(|fee| fee.prioritization_fee).collect();
-            percentiles = Self::calculate_percentiles(&fees);
+    }
+
+    /// Estimates the priority fee for the mining accounts, smoothed with an
+    /// exponential moving average over previous estimates and clamped between
+    /// the configured floor and `--priority-fee`.
+    pub async fn get_dynamic_priority_fee(&self) -> Result<u64, String> {
+        let config = self.fee_config;
+        let sample = self.sample_priority_fee().await?;
+
+        // Smooth over recent rounds
+        let smoothed = {
+            let mut ema = self.fee_ema.lock().unwrap();
+            let smoothed = match *ema {
+                Some(prev) => prev + config.smoothing * (sample as f64 - prev),
+                None => sample as f64,
+            };
+            *ema = Some(smoothed);
+            smoothed.round() as u64
+        };
+
+        // Clamp to floor and ceiling
+        let mut fee = smoothed.max(config.floor);
+        if let Some(max_fee) = self.priority_fee {
+            fee = fee.min(max_fee);
         }
+        debug!(
+            "Priority fee sample {} smoothed {} final {}",
+            sample, smoothed, fee
+        );
 
-        // Default to 75 percentile
-        let fee = *percentiles.get(&75).unwrap_or(&0);
+        // Record estimate
+        let record = FeeRecord {
+            timestamp: chrono::Utc::now().timestamp(),
+            strategy: config.strategy,
+            percentile: config.percentile,
+            sample,
+            smoothed,
+            fee,
+        };
+        if let Err(err) = record_fee(&record) {
+            warn!("Failed to record fee estimate: {}", err);
+        }
         Ok(fee)
     }
 
-    fn calculate_percentiles(fees: &[u64]) -> HashMap<u8, u64> {
-        let mut sorted_fees = fees.to_vec();
-        sorted_fees.sort_unstable();
-        let len = sorted_fees.len();
-        let percentiles = vec![10, 25, 50, 60, 70, 75, 80, 85, 90, 100];
-        percentiles
-            .into_iter()
-            .map(|p| {
-                let index = (p as f64 / 100.0 * len as f64).round() as usize;
-                (p, sorted_fees[index.saturating_sub(1)])
-            })
-            .collect()
+    /// Raw fee estimate for the mining accounts, before smoothing and clamping.
+    pub async fn sample_priority_fee(&self) -> Result<u64, String> {
+        let accounts: Vec<Pubkey> = std::iter::once(eore_api::ID)
+            .chain(BUS_ADDRESSES.iter().copied())
+            .collect();
+        self.fee_estimator()
+            .estimate(&accounts, self.fee_config.percentile)
+            .await
     }
-}
 
-/// Our estimate is the average over the last 20 slots
-fn estimate_prioritization_fee_microlamports(
-    prioritization_fees: Vec<RpcPrioritizationFee>,
-) -> u64 {
-    let prioritization_fees = prioritization_fees
-        .into_iter()
-        .rev()
-        .take(20)
-        .map(
-            |RpcPrioritizationFee {
-                 prioritization_fee, ..
-             }| prioritization_fee,
-        )
-        .collect::<Vec<_>>();
-    if prioritization_fees.is_empty() {
-        panic!("Response does not contain any prioritization fees");
+    /// Compute unit price for a transaction that is not sent through
+    /// `send_and_confirm`: the dynamic estimate when enabled, otherwise
+    /// `--priority-fee`.
+    pub async fn current_priority_fee(&self) -> u64 {
+        let fee = self.priority_fee.unwrap_or(0);
+        if !self.dynamic_fee {
+            return fee;
+        }
+        match self.get_dynamic_priority_fee().await {
+            Ok(fee) => fee,
+            Err(err) => {
+                warn!(
+                    "Failed to get dynamic fee: {}. Falling back to static value: {} microlamports",
+                    err, fee
+                );
+                fee
+            }
+        }
     }
+}
 
-    let prioritization_fee =
-        prioritization_fees.iter().sum::<u64>() / prioritization_fees.len() as u64;
+async fn post(client: &Client, url: &str, body: &Value) -> Result<Value, String> {
+    client
+        .post(url)
+        .json(body)
+        .send()
+        .await
+        .map_err(|err| format!("Priority fee request failed: {}", err))?
+        .json()
+        .await
+        .map_err(|err| format!("Failed to parse priority fee response: {}", err))
+}
 
-    prioritization_fee
+/// Takes the given percentile of fees paid over the most recent slots.
+fn percentile_of_recent_slots(
+    mut fees: Vec<RpcPrioritizationFee>,
+    percentile: u8,
+) -> Result<u64, String> {
+    if fees.is_empty() {
+        return Err("Response does not contain any prioritization fees".to_string());
+    }
+    fees.sort_by_key(|fee| std::cmp::Reverse(fee.slot));
+    let mut recent: Vec<u64> = fees
+        .iter()
+        .take(RECENT_SLOTS)
+        .map(|fee| fee.prioritization_fee)
+        .collect();
+    recent.sort_unstable();
+    let index = (percentile.min(100) as f64 / 100.0 * recent.len() as f64).round() as usize;
+    Ok(recent[index.saturating_sub(1).min(recent.len() - 1)])
 }
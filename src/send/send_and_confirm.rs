@@ -1,39 +1,73 @@
-use std::{str::FromStr, time::Duration};
+use std::time::{Duration, Instant};
 
 use colored::*;
-use eore_api::error::OreError;
 use indicatif::ProgressBar;
 use log::{debug, error, info, warn};
 use solana_client::{
     client_error::{ClientError, ClientErrorKind, Result as ClientResult},
-    rpc_config::RpcSendTransactionConfig,
+    rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig},
 };
 use solana_program::{
     instruction::Instruction,
-    native_token::{lamports_to_sol, sol_to_lamports},
+    native_token::lamports_to_sol,
     pubkey::Pubkey,
 };
 use solana_rpc_client::spinner;
 use solana_sdk::{
     commitment_config::CommitmentLevel,
     compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
     signature::{Signature, Signer},
-    transaction::Transaction,
+    transaction::{Transaction, TransactionError},
 };
 use solana_transaction_status::{TransactionConfirmationStatus, UiTransactionEncoding};
 
-use crate::utils::{get_latest_blockhash_with_retries, ComputeBudget};
+use super::priority_fee::LandedRecord;
+use crate::error::Error;
+use crate::utils::{
+    describe_transaction_error, get_latest_blockhash_with_retries, ComputeBudget,
+    SignatureWatcher,
+};
 use crate::Miner;
-//0.0005=>0.0，取消最小ETH余额限制。cancel min ETH balance limit
-const MIN_ETH_BALANCE: f64 = 0.0;
-
 const RPC_RETRIES: usize = 0;
 const _SIMULATION_RETRIES: usize = 4;
 const GATEWAY_RETRIES: usize = 150;
 const CONFIRM_RETRIES: usize = 8;
 
+const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+
 const CONFIRM_DELAY: u64 = 500;
-const GATEWAY_DELAY: u64 = 0;
+const GATEWAY_DELAY: u64 = 200;
+const MAX_GATEWAY_DELAY: u64 = 2_000;
+const GATEWAY_DEADLINE: u64 = 120;
+
+/// Limits on how long `send_and_confirm` keeps rebroadcasting a transaction.
+#[derive(Clone, Copy, Debug)]
+pub struct SendPolicy {
+    pub max_attempts: usize,
+    pub deadline: Duration,
+    pub backoff: Duration,
+}
+
+impl Default for SendPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: GATEWAY_RETRIES,
+            deadline: Duration::from_secs(GATEWAY_DEADLINE),
+            backoff: Duration::from_millis(GATEWAY_DELAY),
+        }
+    }
+}
+
+impl SendPolicy {
+    /// Delay before the next rebroadcast, doubling up to `MAX_GATEWAY_DELAY`.
+    fn backoff(&self, attempt: usize) -> Duration {
+        let max_backoff = Duration::from_millis(MAX_GATEWAY_DELAY).max(self.backoff);
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1) as u32))
+            .min(max_backoff)
+    }
+}
 
 impl Miner {
     pub async fn send_and_confirm(
@@ -41,7 +75,20 @@ impl Miner {
         ixs: &[Instruction],
         compute_budget: ComputeBudget,
         skip_confirm: bool,
-    ) -> ClientResult<Signature> {
+    ) -> Result<Signature, Error> {
+        self.send_and_confirm_with_deadline(ixs, compute_budget, skip_confirm, None)
+            .await
+    }
+
+    /// Sends the instructions like `send_and_confirm`, escalating the priority fee
+    /// faster as the landing deadline approaches.
+    pub async fn send_and_confirm_with_deadline(
+        &self,
+        ixs: &[Instruction],
+        compute_budget: ComputeBudget,
+        skip_confirm: bool,
+        landing_deadline: Option<Instant>,
+    ) -> Result<Signature, Error> {
         debug!("Starting send_and_confirm with {} instructions", ixs.len());
 
         let progress_bar = spinner::new_progress_bar();
@@ -53,24 +100,53 @@ impl Miner {
         debug!("Using fee payer: {}", fee_payer.pubkey());
         debug!("RPC client URL: {}", client.url());
 
-        // Return error, if balance is zero
-        self.check_balance().await;
-
         // Set compute budget
         let mut final_ixs = vec![];
-        match compute_budget {
+        let cu_limit = match compute_budget {
             ComputeBudget::Dynamic => {
                 debug!("Using dynamic compute budget");
-                todo!("simulate tx")
+                progress_bar.set_message("Simulating transaction...");
+                let cus = match self.simulate_compute_units(ixs, &fee_payer.pubkey()).await {
+                    Ok(cus) => cus,
+                    // Let the simulation report show the failure
+                    Err(_) if self.simulate => MAX_COMPUTE_UNITS,
+                    Err(err) => {
+                        // The simulation runs behind a compute unit limit instruction
+                        let description = match err.get_transaction_error() {
+                            Some(tx_err) => {
+                                let program_ids: Vec<Pubkey> =
+                                    std::iter::once(solana_sdk::compute_budget::ID)
+                                        .chain(ixs.iter().map(|ix| ix.program_id))
+                                        .collect();
+                                describe_transaction_error(&tx_err, &program_ids)
+                            }
+                            None => err.to_string(),
+                        };
+                        log_error(&progress_bar, &description, true);
+                        return Err(err.into());
+                    }
+                };
+                debug!("Using simulated compute budget: {} CUs", cus);
+                final_ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(cus));
+                cus
             }
             ComputeBudget::Fixed(cus) => {
                 debug!("Using fixed compute budget: {} CUs", cus);
-                final_ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(cus))
+                final_ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(cus));
+                cus
+            }
+        };
+
+        // Stay within the spending limits
+        if !self.simulate {
+            if let Err(err) = self.check_spending(cu_limit).await {
+                log_error(&progress_bar, &err.to_string(), true);
+                return Err(err);
             }
         }
 
         // Set compute unit price
-        let priority_fee = self.priority_fee.unwrap_or(0);
+        let mut priority_fee = self.priority_fee.unwrap_or(0);
         debug!("Setting compute unit price: {} microlamports", priority_fee);
         final_ixs.push(ComputeBudgetInstruction::set_compute_unit_price(
             priority_fee,
@@ -107,6 +183,7 @@ impl Miner {
                     .collect::<Vec<_>>()
             );
         }
+        let program_ids: Vec<Pubkey> = final_ixs.iter().map(|ix| ix.program_id).collect();
 
         // Build tx
         debug!("Building transaction with config: skip_preflight=true, commitment=Confirmed");
@@ -119,23 +196,52 @@ impl Miner {
         };
         let mut tx = Transaction::new_with_payer(&final_ixs, Some(&fee_payer.pubkey()));
 
+        // Watch signatures over one websocket for every rebroadcast
+        let watcher = match (&self.ws_url, skip_confirm || self.simulate) {
+            (Some(ws_url), false) => SignatureWatcher::connect(ws_url).await,
+            _ => None,
+        };
+
         // Submit tx
+        let policy = self.send_policy;
+        let started = Instant::now();
         let mut attempts = 0;
+        let mut last_valid_block_height = 0;
+        let mut blockhash = Hash::default();
+        let mut sent: Vec<(Signature, u64)> = vec![];
         loop {
+            // Give up once the retry policy is exhausted
+            if attempts >= policy.max_attempts {
+                let err = Error::SendAttemptsExhausted(attempts);
+                log_error(&progress_bar, &err.to_string(), true);
+                return Err(err);
+            }
+            if started.elapsed() >= policy.deadline {
+                let err = Error::SendDeadlineExceeded(started.elapsed().as_secs());
+                log_error(&progress_bar, &err.to_string(), true);
+                return Err(err);
+            }
+
             debug!("Transaction attempt #{}", attempts);
             progress_bar.set_message(format!("Submitting transaction... (attempt {})", attempts,));
 
-            // Sign tx with a new blockhash (after approximately ~45 sec)
-            if attempts % 10 == 0 {
+            // Refresh the blockhash once the previous one has expired
+            let blockhash_expired = attempts == 0
+                || match client.get_block_height().await {
+                    Ok(block_height) => block_height > last_valid_block_height,
+                    Err(_) => false,
+                };
+            let mut fee = priority_fee;
+            if blockhash_expired {
                 debug!(
                     "Refreshing blockhash and recomputing fees (attempt {})",
                     attempts
                 );
 
-                // Reset the compute unit price
+                // Reset the compute unit price, never below the escalated price
                 if self.dynamic_fee {
                     debug!("Computing dynamic priority fee");
-                    let fee = match self.get_dynamic_priority_fee().await {
+                    let estimate = match self.get_dynamic_priority_fee().await {
                         Ok(fee) => {
                             debug!("Dynamic priority fee computed: {} microlamports", fee);
                             progress_bar.println(format!("  Priority fee: {} microlamports", fee));
@@ -154,30 +260,63 @@ impl Miner {
                             fee
                         }
                     };
-
-                    final_ixs.remove(1);
-                    final_ixs.insert(1, ComputeBudgetInstruction::set_compute_unit_price(fee));
-                    tx = Transaction::new_with_payer(&final_ixs, Some(&fee_payer.pubkey()));
+                    fee = if attempts == 0 {
+                        estimate
+                    } else {
+                        estimate.max(priority_fee)
+                    };
                 }
 
-                // Resign the tx
                 debug!("Getting latest blockhash");
-                let (hash, slot) = get_latest_blockhash_with_retries(&client).await?;
-                debug!("Got blockhash {} at slot {}", hash, slot);
+                let (hash, block_height) = get_latest_blockhash_with_retries(&client).await?;
+                debug!("Got blockhash {} valid until block {}", hash, block_height);
+                blockhash = hash;
+                last_valid_block_height = block_height;
+            } else if let Some(cap) = self.priority_fee {
+                // Raise the price of an unconfirmed rebroadcast
+                let remaining = landing_deadline
+                    .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+                fee = self.fee_config.escalate(priority_fee, cap, remaining);
+                if fee > priority_fee {
+                    debug!(
+                        "Escalating priority fee from {} to {} microlamports",
+                        priority_fee, fee
+                    );
+                    progress_bar.println(format!("  Priority fee: {} microlamports", fee));
+                }
+            }
 
+            // Resign the tx
+            if blockhash_expired || fee != priority_fee {
+                priority_fee = fee;
+                final_ixs[1] = ComputeBudgetInstruction::set_compute_unit_price(fee);
+                tx = Transaction::new_with_payer(&final_ixs, Some(&fee_payer.pubkey()));
                 if signer.pubkey() == fee_payer.pubkey() {
                     debug!("Signing transaction with single signer");
-                    tx.sign(&[&signer], hash);
+                    tx.sign(&[&signer], blockhash);
                 } else {
                     debug!("Signing transaction with both signer and fee payer");
-                    tx.sign(&[&signer, &fee_payer], hash);
+                    tx.sign(&[&signer, &fee_payer], blockhash);
                 }
             }
 
+            // Simulate instead of sending
+            if self.simulate {
+                progress_bar.finish_and_clear();
+                self.print_simulation(&tx, cu_limit, priority_fee).await?;
+                return Ok(tx.signatures[0]);
+            }
+
+            // Subscribe to the signature before sending so the notification is not missed
+            let subscription = match &watcher {
+                Some(watcher) => watcher.subscribe(&tx.signatures[0]).await,
+                None => None,
+            };
+
             // Send transaction
             attempts += 1;
             debug!("Sending transaction to RPC");
-            match client.send_and_confirm_transaction(&tx).await {
+            match client.send_transaction_with_config(&tx, send_cfg).await {
                 Ok(sig) => {
                     debug!("Transaction sent successfully: {}", sig);
 
@@ -187,94 +326,108 @@ impl Miner {
                         progress_bar.finish_with_message(format!("Sent: {}", sig));
                         return Ok(sig);
                     }
+                    if !sent.iter().any(|(s, _)| *s == sig) {
+                        sent.push((sig, priority_fee));
+                    }
 
-                    // Confirm transaction
-                    'confirm: for confirm_attempt in 0..CONFIRM_RETRIES {
+                    // Wait for the websocket notification, fall back to polling below
+                    let watched = subscription.is_some();
+                    if let Some(subscription) = subscription {
+                        let timeout = Duration::from_millis(CONFIRM_DELAY * CONFIRM_RETRIES as u64);
+                        match subscription.wait(timeout).await {
+                            Some(Ok(())) => {
+                                debug!("Transaction confirmed via signature subscription");
+                                self.finish_landed(
+                                    &progress_bar,
+                                    sig,
+                                    cu_limit,
+                                    priority_fee,
+                                    attempts,
+                                );
+                                return Ok(sig);
+                            }
+                            Some(Err(err)) => {
+                                self.record_fee_paid(sig, cu_limit, priority_fee, attempts);
+                                let description = describe_transaction_error(&err, &program_ids);
+                                error!("Transaction error: {}", description);
+                                log_error(&progress_bar, &description, true);
+                                return Err(ClientError {
+                                    request: None,
+                                    kind: ClientErrorKind::TransactionError(err),
+                                }
+                                .into());
+                            }
+                            None => debug!("No signature notification, checking status"),
+                        }
+                    }
+
+                    // Confirm transaction. Earlier rebroadcasts at a lower price may
+                    // still land, so every signature sent so far is checked. After
+                    // waiting on the websocket, a single check is enough.
+                    let sigs: Vec<Signature> = sent.iter().map(|(sig, _)| *sig).collect();
+                    let (confirm_retries, confirm_delay) = if watched {
+                        (1, 0)
+                    } else {
+                        (CONFIRM_RETRIES, CONFIRM_DELAY)
+                    };
+                    for confirm_attempt in 0..confirm_retries {
                         debug!(
-                            "Confirmation attempt #{} for signature {}",
-                            confirm_attempt, sig
+                            "Confirmation attempt #{} for {} signatures",
+                            confirm_attempt,
+                            sigs.len()
                         );
-                        tokio::time::sleep(Duration::from_millis(CONFIRM_DELAY)).await;
-                        match client.get_signature_statuses(&[sig]).await {
+                        tokio::time::sleep(Duration::from_millis(confirm_delay)).await;
+                        match client.get_signature_statuses(&sigs).await {
                             Ok(signature_statuses) => {
                                 debug!("Got signature statuses: {:?}", signature_statuses);
-                                for status in signature_statuses.value {
-                                    if let Some(status) = status {
-                                        if let Some(err) = status.err {
-                                            debug!("Transaction error: {:?}", err);
-                                            match err {
-                                                // Instruction error
-                                                solana_sdk::transaction::TransactionError::InstructionError(_, err) => {
-                                                    match err {
-                                                        // Custom instruction error, parse into OreError
-                                                        solana_program::instruction::InstructionError::Custom(err_code) => {
-                                                            match err_code {
-                                                                e if e == OreError::NeedsReset as u32 => {
-                                                                    debug!("Needs reset error, retrying transaction");
-                                                                    attempts = 0;
-                                                                    log_error(&progress_bar, "Needs reset. Retrying...", false);
-                                                                    break 'confirm;
-                                                                },
-                                                                _ => {
-                                                                    error!("Custom instruction error: {}", err);
-                                                                    log_error(&progress_bar, &err.to_string(), true);
-                                                                    return Err(ClientError {
-                                                                        request: None,
-                                                                        kind: ClientErrorKind::Custom(err.to_string()),
-                                                                    });
-                                                                }
-                                                            }
-                                                        },
-
-                                                        // Non custom instruction error, return
-                                                        _ => {
-                                                            error!("Non-custom instruction error: {}", err);
-                                                            log_error(&progress_bar, &err.to_string(), true);
-                                                            return Err(ClientError {
-                                                                request: None,
-                                                                kind: ClientErrorKind::Custom(err.to_string()),
-                                                            });
-                                                        }
-                                                    }
-                                                },
-
-                                                // Non instruction error, return
-                                                _ => {
-                                                    error!("Non-instruction error: {}", err);
-                                                    log_error(&progress_bar, &err.to_string(), true);
-                                                    return Err(ClientError {
-                                                        request: None,
-                                                        kind: ClientErrorKind::Custom(err.to_string()),
-                                                    });
-                                                }
+                                let mut tx_err = None;
+                                for (i, status) in signature_statuses.value.into_iter().enumerate()
+                                {
+                                    let Some(status) = status else {
+                                        continue;
+                                    };
+                                    if let Some(err) = status.err {
+                                        tx_err = Some((i, err));
+                                    } else if let Some(confirmation) = status.confirmation_status {
+                                        debug!(
+                                            "Transaction confirmation status: {:?}",
+                                            confirmation
+                                        );
+                                        match confirmation {
+                                            TransactionConfirmationStatus::Processed => {
+                                                debug!("Transaction processed but not confirmed");
                                             }
-                                        } else if let Some(confirmation) =
-                                            status.confirmation_status
-                                        {
-                                            debug!(
-                                                "Transaction confirmation status: {:?}",
-                                                confirmation
-                                            );
-                                            match confirmation {
-                                                TransactionConfirmationStatus::Processed => {
-                                                    debug!(
-                                                        "Transaction processed but not confirmed"
-                                                    );
-                                                }
-                                                TransactionConfirmationStatus::Confirmed
-                                                | TransactionConfirmationStatus::Finalized => {
-                                                    debug!("Transaction confirmed/finalized");
-                                                    progress_bar.finish_with_message(format!(
-                                                        "{} {}",
-                                                        "OK".bold().green(),
-                                                        sig
-                                                    ));
-                                                    return Ok(sig);
-                                                }
+                                            TransactionConfirmationStatus::Confirmed
+                                            | TransactionConfirmationStatus::Finalized => {
+                                                debug!("Transaction confirmed/finalized");
+                                                let (sig, fee) = sent[i];
+                                                self.finish_landed(
+                                                    &progress_bar,
+                                                    sig,
+                                                    cu_limit,
+                                                    fee,
+                                                    attempts,
+                                                );
+                                                return Ok(sig);
                                             }
                                         }
                                     }
                                 }
+
+                                // None of the sent transactions landed successfully
+                                if let Some((i, err)) = tx_err {
+                                    let (sig, fee) = sent[i];
+                                    self.record_fee_paid(sig, cu_limit, fee, attempts);
+                                    let description =
+                                        describe_transaction_error(&err, &program_ids);
+                                    error!("Transaction error: {}", description);
+                                    log_error(&progress_bar, &description, true);
+                                    return Err(ClientError {
+                                        request: None,
+                                        kind: ClientErrorKind::TransactionError(err),
+                                    }
+                                    .into());
+                                }
                             }
 
                             // Handle confirmation errors
@@ -288,33 +441,120 @@ impl Miner {
 
                 // Handle submit errors
                 Err(err) => {
-                    error!("Error submitting transaction: {}", err);
-                    log_error(&progress_bar, &err.kind().to_string(), false);
+                    if let Some(subscription) = subscription {
+                        subscription.unsubscribe().await;
+                    }
+                    match err.get_transaction_error() {
+                        // The transaction landed and failed, resubmitting will not help
+                        Some(tx_err) if tx_err != TransactionError::BlockhashNotFound => {
+                            let description = describe_transaction_error(&tx_err, &program_ids);
+                            error!("Transaction error: {}", description);
+                            log_error(&progress_bar, &description, true);
+                            return Err(err.into());
+                        }
+                        _ => {
+                            error!("Error submitting transaction: {}", err);
+                            log_error(&progress_bar, &err.kind().to_string(), false);
+                        }
+                    }
                 }
             }
+
+            // Back off before rebroadcasting
+            tokio::time::sleep(policy.backoff(attempts)).await;
         }
     }
 
-    pub async fn check_balance(&self) {
-        debug!("Checking balance for signer: {}", self.signer().pubkey());
-        let balance = self
+    /// Marks the transaction as landed and records the fee paid for it.
+    fn finish_landed(
+        &self,
+        progress_bar: &ProgressBar,
+        sig: Signature,
+        cu_limit: u32,
+        cu_price: u64,
+        attempts: usize,
+    ) {
+        let fee = self.record_fee_paid(sig, cu_limit, cu_price, attempts);
+        progress_bar.finish_with_message(format!(
+            "{} {} (fee {} ETH, {} attempts)",
+            "OK".bold().green(),
+            sig,
+            lamports_to_sol(fee),
+            attempts
+        ));
+    }
+
+    /// Records the fee paid by a landed transaction, whether it succeeded or
+    /// failed, and returns it in lamports.
+    fn record_fee_paid(&self, sig: Signature, cu_limit: u32, cu_price: u64, attempts: usize) -> u64 {
+        let fee = self.fee_cost(cu_limit, cu_price);
+        self.record_fee(LandedRecord {
+            timestamp: chrono::Utc::now().timestamp(),
+            signature: sig.to_string(),
+            fee_payer: self.fee_payer().pubkey().to_string(),
+            signer: self.signer().pubkey().to_string(),
+            cu_limit,
+            cu_price,
+            fee,
+            attempts,
+        });
+        fee
+    }
+
+    /// Simulates the instructions and returns the compute units consumed plus the
+    /// configured safety margin. Simulation failures are returned before anything
+    /// is broadcast.
+    pub async fn simulate_compute_units(
+        &self,
+        ixs: &[Instruction],
+        fee_payer: &Pubkey,
+    ) -> ClientResult<u32> {
+        let mut sim_ixs = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+            MAX_COMPUTE_UNITS,
+        )];
+        sim_ixs.extend_from_slice(ixs);
+        let tx = Transaction::new_with_payer(&sim_ixs, Some(fee_payer));
+        let sim = self
             .rpc_client
-            .get_balance(&self.signer().pubkey())
-            .await
-            .unwrap_or(0);
-        debug!("Current balance: {} ETH", lamports_to_sol(balance));
-        if balance < sol_to_lamports(MIN_ETH_BALANCE) {
-            error!(
-                "Insufficient balance: {} ETH < {} ETH",
-                lamports_to_sol(balance),
-                MIN_ETH_BALANCE
-            );
-            panic!(
-                "Insufficient balance: {} ETH < {} ETH",
-                lamports_to_sol(balance),
-                MIN_ETH_BALANCE
-            );
+            .simulate_transaction_with_config(
+                &tx,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    commitment: Some(self.rpc_client.commitment()),
+                    ..Default::default()
+                },
+            )
+            .await?
+            .value;
+
+        // Surface simulation errors
+        if let Some(err) = sim.err {
+            if let Some(logs) = sim.logs {
+                for log in logs {
+                    debug!("  {}", log);
+                }
+            }
+            return Err(ClientError {
+                request: None,
+                kind: ClientErrorKind::TransactionError(err),
+            });
         }
+
+        // Add margin on top of the consumed units
+        let units_consumed = sim.units_consumed.ok_or_else(|| ClientError {
+            request: None,
+            kind: ClientErrorKind::Custom("Simulation did not report units consumed".into()),
+        })?;
+        let cus = units_consumed
+            .saturating_mul(100 + self.compute_unit_margin)
+            .saturating_div(100)
+            .min(MAX_COMPUTE_UNITS as u64) as u32;
+        debug!(
+            "Simulation consumed {} CUs, limit set to {} CUs ({}% margin)",
+            units_consumed, cus, self.compute_unit_margin
+        );
+        Ok(cus)
     }
 }
 
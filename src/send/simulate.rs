@@ -0,0 +1,169 @@
+use colored::*;
+use solana_client::{client_error::Result as ClientResult, rpc_config::RpcSimulateTransactionConfig};
+use solana_program::native_token::lamports_to_sol;
+use solana_sdk::transaction::Transaction;
+use tabled::{
+    settings::{
+        object::{Columns, Rows},
+        Alignment, Remove, Style,
+    },
+    Table,
+};
+
+use crate::{
+    utils::{describe_transaction_error, TableData, TableSectionTitle},
+    Miner,
+};
+
+impl Miner {
+    /// Simulates a fully built and signed transaction and prints what it would do.
+    /// Nothing is broadcast.
+    pub async fn print_simulation(
+        &self,
+        tx: &Transaction,
+        cu_limit: u32,
+        cu_price: u64,
+    ) -> ClientResult<()> {
+        let sim = self
+            .rpc_client
+            .simulate_transaction_with_config(
+                tx,
+                RpcSimulateTransactionConfig {
+                    // Signatures other parties still have to add cannot be verified
+                    sig_verify: tx.is_signed(),
+                    commitment: Some(self.rpc_client.commitment()),
+                    ..Default::default()
+                },
+            )
+            .await?
+            .value;
+        let base_fee = self.rpc_client.get_fee_for_message(&tx.message).await?;
+        let priority_fee = (cu_limit as u64)
+            .saturating_mul(cu_price)
+            .div_ceil(1_000_000);
+
+        // Transaction summary
+        let message = &tx.message;
+        let mut data = vec![];
+        data.push(TableData {
+            key: "Fee payer".to_string(),
+            value: message.account_keys[0].to_string(),
+        });
+        data.push(TableData {
+            key: "Signers".to_string(),
+            value: message.header.num_required_signatures.to_string(),
+        });
+        data.push(TableData {
+            key: "Size".to_string(),
+            value: format!(
+                "{} bytes",
+                bincode::serialized_size(tx).unwrap_or_default()
+            ),
+        });
+        data.push(TableData {
+            key: "CU limit".to_string(),
+            value: cu_limit.to_string(),
+        });
+        data.push(TableData {
+            key: "CU consumed".to_string(),
+            value: sim
+                .units_consumed
+                .map(|units| units.to_string())
+                .unwrap_or("–".to_string()),
+        });
+        data.push(TableData {
+            key: "CU price".to_string(),
+            value: format!("{} microlamports", cu_price),
+        });
+        data.push(TableData {
+            key: "Fee".to_string(),
+            value: format!(
+                "{} ETH (base {} + priority {})",
+                lamports_to_sol(base_fee + priority_fee),
+                lamports_to_sol(base_fee),
+                lamports_to_sol(priority_fee)
+            ),
+        });
+        data.push(TableData {
+            key: "Status".to_string(),
+            value: match &sim.err {
+                None => "Success".bold().green().to_string(),
+                Some(err) => {
+                    let program_ids: Vec<_> = message
+                        .instructions
+                        .iter()
+                        .map(|ix| message.account_keys[ix.program_id_index as usize])
+                        .collect();
+                    format!(
+                        "{} {}",
+                        "Failed".bold().red(),
+                        describe_transaction_error(err, &program_ids)
+                    )
+                }
+            },
+        });
+
+        // Instructions
+        let len1 = data.len();
+        let mut section_rows = vec![];
+        for (i, ix) in message.instructions.iter().enumerate() {
+            section_rows.push(data.len());
+            data.push(TableData {
+                key: format!("#{} Program", i),
+                value: message.account_keys[ix.program_id_index as usize].to_string(),
+            });
+            for (j, index) in ix.accounts.iter().enumerate() {
+                let index = *index as usize;
+                let mut flags = vec![];
+                if message.is_signer(index) {
+                    flags.push("signer");
+                }
+                if message.is_maybe_writable(index, None) {
+                    flags.push("writable");
+                }
+                data.push(TableData {
+                    key: format!("Account {}", j),
+                    value: if flags.is_empty() {
+                        message.account_keys[index].to_string()
+                    } else {
+                        format!("{} ({})", message.account_keys[index], flags.join(", "))
+                    },
+                });
+            }
+            data.push(TableData {
+                key: "Data".to_string(),
+                value: format!("{} bytes", ix.data.len()),
+            });
+        }
+
+        // Program logs
+        let len2 = data.len();
+        for (i, log) in sim.logs.unwrap_or_default().into_iter().enumerate() {
+            data.push(TableData {
+                key: i.to_string(),
+                value: log,
+            });
+        }
+
+        // Build table
+        let has_logs = data.len() > len2;
+        let mut table = Table::new(data);
+        table.with(Remove::row(Rows::first()));
+        table.modify(Columns::single(1), Alignment::left());
+        table.with(Style::blank());
+        table.section_title(0, "Simulation");
+        table.section_title(len1, "Instructions");
+        for row in section_rows.iter().skip(1) {
+            table.section_title(*row, "");
+        }
+        if has_logs {
+            table.section_title(len2, "Logs");
+        }
+        println!("\n{table}\n");
+        println!(
+            "{} Simulation only. The transaction was not broadcast.\n",
+            "NOTE".bold().yellow()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+use std::sync::{Arc, Mutex};
+
+use colored::*;
+use log::{debug, warn};
+use solana_program::native_token::{lamports_to_sol, sol_to_lamports};
+use solana_sdk::signature::Signer;
+
+use super::priority_fee::{read_landed_history, record_landed, LandedRecord};
+use crate::{error::Error, Miner};
+
+const ONE_DAY: i64 = 86_400;
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// Hard limits on what the fee payer may spend.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SpendLimits {
+    /// Maximum fees in ETH the fee payer may spend over any 24 hours.
+    pub max_fee_per_day: Option<f64>,
+    /// Pause sending while the fee payer holds less than this many ETH.
+    pub min_balance: f64,
+    /// Price of one BITZ in ETH. Enables the mining profitability check.
+    pub bitz_price: Option<f64>,
+    /// Count only the fees of the signer against the daily budget. Batch
+    /// mining gives each child process its share of the budget this way.
+    pub budget_per_signer: bool,
+}
+
+/// Fees paid within the last day as `(timestamp, lamports)`. The
+/// landed history is read once to seed it, later fees are added as they are
+/// paid, so checking the budget never re-reads the history.
+#[derive(Clone, Default)]
+pub struct FeeLedger {
+    fees: Arc<Mutex<Option<Vec<(i64, u64)>>>>,
+}
+
+impl Miner {
+    /// Fails if sending a transaction with the given budget could break the
+    /// spending limits. The fee is assumed to be paid at the `--priority-fee` cap,
+    /// so fee escalation can never push spending past the daily budget. Without
+    /// a cap, the highest current dynamic fee is assumed instead.
+    pub async fn check_spending(&self, cu_limit: u32) -> Result<(), Error> {
+        let fee_payer = self.fee_payer().pubkey();
+
+        // Pause while the fee payer is low on funds
+        let balance = self.rpc_client.get_balance(&fee_payer).await?;
+        debug!("Fee payer balance: {} ETH", lamports_to_sol(balance));
+        if balance < sol_to_lamports(self.spend_limits.min_balance) {
+            println!(
+                "\x07{} Fee payer {} balance {} ETH is below {} ETH. Sending is paused until it is topped up.",
+                "ALERT".bold().red(),
+                fee_payer,
+                lamports_to_sol(balance),
+                self.spend_limits.min_balance
+            );
+            return Err(Error::LowBalance(lamports_to_sol(balance)));
+        }
+
+        // Stay within the daily fee budget
+        if let Some(max_fee_per_day) = self.spend_limits.max_fee_per_day {
+            let spent = self.fees_spent_last_day(&fee_payer.to_string());
+            let worst_case = self.fee_cost(cu_limit, self.worst_case_cu_price().await);
+            if lamports_to_sol(spent + worst_case) > max_fee_per_day {
+                println!(
+                    "{} Fee payer {} spent {} ETH in the last 24h, the budget is {} ETH.",
+                    "ALERT".bold().red(),
+                    fee_payer,
+                    lamports_to_sol(spent),
+                    max_fee_per_day
+                );
+                return Err(Error::FeeBudgetExhausted(lamports_to_sol(spent)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fee in lamports of a transaction with the given compute budget and price.
+    pub fn fee_cost(&self, cu_limit: u32, cu_price: u64) -> u64 {
+        let signatures = if self.signer().pubkey() == self.fee_payer().pubkey() {
+            1
+        } else {
+            2
+        };
+        transaction_fee(signatures, cu_limit, cu_price)
+    }
+
+    /// The compute unit price the next transaction is expected to pay.
+    pub fn expected_cu_price(&self) -> u64 {
+        let ema = if self.dynamic_fee {
+            *self.fee_ema.lock().unwrap()
+        } else {
+            None
+        };
+        match (ema, self.priority_fee) {
+            (Some(ema), Some(cap)) => (ema.round() as u64).max(self.fee_config.floor).min(cap),
+            (Some(ema), None) => (ema.round() as u64).max(self.fee_config.floor),
+            (None, cap) => cap.unwrap_or(0),
+        }
+    }
+
+    /// The highest compute unit price the next transaction may pay: the
+    /// `--priority-fee` cap, or without one the larger of the latest dynamic
+    /// fee sample and its moving average.
+    async fn worst_case_cu_price(&self) -> u64 {
+        if let Some(cap) = self.priority_fee {
+            return cap;
+        }
+        if !self.dynamic_fee {
+            return 0;
+        }
+        let sample = self.sample_priority_fee().await.unwrap_or(0);
+        sample
+            .max(self.fee_config.floor)
+            .max(self.expected_cu_price())
+    }
+
+    /// Lamports paid in fees by the fee payer over the last 24 hours.
+    fn fees_spent_last_day(&self, fee_payer: &str) -> u64 {
+        let since = chrono::Utc::now().timestamp() - ONE_DAY;
+        let mut fees = self.fee_ledger.fees.lock().unwrap();
+        let fees = fees.get_or_insert_with(|| {
+            let signer = self.signer().pubkey().to_string();
+            read_landed_history()
+                .unwrap_or_default()
+                .iter()
+                .filter(|record| record.timestamp >= since && record.fee_payer == fee_payer)
+                .filter(|record| !self.spend_limits.budget_per_signer || record.signer == signer)
+                .map(|record| (record.timestamp, record.fee))
+                .collect()
+        });
+        fees.retain(|(timestamp, _)| *timestamp >= since);
+        fees.iter().map(|(_, fee)| fee).sum()
+    }
+
+    /// Records a fee the fee payer paid, in the landed history and against
+    /// the daily budget.
+    pub fn record_fee(&self, record: LandedRecord) {
+        if let Err(err) = record_landed(&record) {
+            warn!("Failed to record landed transaction: {}", err);
+        }
+        // Before the ledger is seeded, the fee is read back from the history
+        if let Some(fees) = self.fee_ledger.fees.lock().unwrap().as_mut() {
+            fees.push((record.timestamp, record.fee));
+        }
+    }
+}
+
+/// Fee in lamports of a transaction with the given number of signatures,
+/// compute budget and price.
+pub fn transaction_fee(signatures: u64, cu_limit: u32, cu_price: u64) -> u64 {
+    let priority_fee = (cu_limit as u64)
+        .saturating_mul(cu_price)
+        .div_ceil(1_000_000);
+    LAMPORTS_PER_SIGNATURE * signatures + priority_fee
+}
@@ -0,0 +1,200 @@
+use b64::FromBase64;
+use eore_api::{event::MineEvent, instruction::OreInstruction};
+use eore_boost_api::instruction::BoostInstruction;
+use ore_pool_api::instruction::PoolInstruction;
+use solana_program::{
+    native_token::lamports_to_sol, pubkey::Pubkey, system_instruction::SystemInstruction,
+};
+use spl_token::{amount_to_ui_amount, instruction::TokenInstruction};
+
+use super::amount_u64_to_string;
+
+/// An instruction decoded into its program, name and arguments.
+pub struct DecodedInstruction {
+    pub program: String,
+    pub name: String,
+    pub args: Vec<(String, String)>,
+}
+
+impl DecodedInstruction {
+    fn new(program: &str, name: impl ToString) -> Self {
+        Self {
+            program: program.to_string(),
+            name: name.to_string(),
+            args: vec![],
+        }
+    }
+
+    fn arg(mut self, key: &str, value: impl ToString) -> Self {
+        self.args.push((key.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Decodes the instruction data of the BITZ, boost, pool, token and runtime
+/// programs. Instructions of other programs are shown by their program id.
+pub fn decode_instruction(program_id: &Pubkey, data: &[u8]) -> DecodedInstruction {
+    if *program_id == eore_api::ID {
+        decode_bitz(data)
+    } else if *program_id == eore_boost_api::ID {
+        decode_boost(data)
+    } else if *program_id == ore_pool_api::ID {
+        decode_pool(data)
+    } else if *program_id == spl_token::ID {
+        decode_token(data)
+    } else if *program_id == spl_associated_token_account::ID {
+        let name = match data.first() {
+            None | Some(0) => "Create",
+            Some(1) => "CreateIdempotent",
+            Some(_) => "RecoverNested",
+        };
+        DecodedInstruction::new("Associated Token", name)
+    } else if *program_id == solana_sdk::compute_budget::ID {
+        decode_compute_budget(data)
+    } else if *program_id == solana_program::system_program::ID {
+        decode_system(data)
+    } else {
+        DecodedInstruction::new(&program_id.to_string(), "Unknown")
+            .arg("Data", format!("{} bytes", data.len()))
+    }
+}
+
+/// Mine event returned by the BITZ program, parsed from the transaction logs.
+pub fn mine_event_from_logs(log_messages: &[String]) -> Option<MineEvent> {
+    let return_log = log_messages
+        .iter()
+        .find(|log| log.starts_with("Program return: "))?;
+    let return_data = return_log.strip_prefix(&format!("Program return: {} ", eore_api::ID))?;
+    let return_data = return_data.from_base64().ok()?;
+    Some(*MineEvent::from_bytes(&return_data))
+}
+
+fn decode_bitz(data: &[u8]) -> DecodedInstruction {
+    let Some(ix) = data.first().and_then(|d| OreInstruction::try_from(*d).ok()) else {
+        return unknown_discriminator("BITZ", data);
+    };
+    let decoded = DecodedInstruction::new("BITZ", format!("{:?}", ix));
+    match ix {
+        OreInstruction::Claim => decoded.arg("Amount", bitz_amount(data, 1)),
+        OreInstruction::Mine => {
+            let (Some(digest), Some(nonce)) = (bytes::<16>(data, 1), bytes::<8>(data, 17)) else {
+                return decoded;
+            };
+            let difficulty = drillx::Solution::new(digest, nonce).to_hash().difficulty();
+            decoded
+                .arg("Digest", hex(&digest))
+                .arg("Nonce", u64::from_le_bytes(nonce))
+                .arg("Difficulty", difficulty)
+        }
+        _ => decoded,
+    }
+}
+
+fn decode_boost(data: &[u8]) -> DecodedInstruction {
+    let Some(ix) = data
+        .first()
+        .and_then(|d| BoostInstruction::try_from(*d).ok())
+    else {
+        return unknown_discriminator("Boost", data);
+    };
+    let decoded = DecodedInstruction::new("Boost", format!("{:?}", ix));
+    match ix {
+        BoostInstruction::Claim | BoostInstruction::Deposit | BoostInstruction::Withdraw => {
+            decoded.arg("Amount", raw_amount(data, 1))
+        }
+        _ => decoded,
+    }
+}
+
+fn decode_pool(data: &[u8]) -> DecodedInstruction {
+    let Some(ix) = data
+        .first()
+        .and_then(|d| PoolInstruction::try_from(*d).ok())
+    else {
+        return unknown_discriminator("Pool", data);
+    };
+    let decoded = DecodedInstruction::new("Pool", format!("{:?}", ix));
+    match ix {
+        PoolInstruction::Claim => decoded.arg("Amount", bitz_amount(data, 1)),
+        PoolInstruction::Attribute => decoded.arg("Total balance", bitz_amount(data, 1)),
+        _ => decoded,
+    }
+}
+
+fn decode_token(data: &[u8]) -> DecodedInstruction {
+    let Ok(ix) = TokenInstruction::unpack(data) else {
+        return unknown_discriminator("Token", data);
+    };
+    match ix {
+        TokenInstruction::Transfer { amount } => {
+            DecodedInstruction::new("Token", "Transfer").arg("Amount", amount)
+        }
+        TokenInstruction::TransferChecked { amount, decimals } => {
+            DecodedInstruction::new("Token", "TransferChecked")
+                .arg("Amount", amount_to_ui_amount(amount, decimals))
+        }
+        TokenInstruction::MintTo { amount } => {
+            DecodedInstruction::new("Token", "MintTo").arg("Amount", amount)
+        }
+        TokenInstruction::Burn { amount } => {
+            DecodedInstruction::new("Token", "Burn").arg("Amount", amount)
+        }
+        ix => {
+            let name = format!("{:?}", ix);
+            let name = name.split([' ', '(', '{']).next().unwrap_or_default();
+            DecodedInstruction::new("Token", name)
+        }
+    }
+}
+
+fn decode_compute_budget(data: &[u8]) -> DecodedInstruction {
+    match data.first() {
+        Some(2) => DecodedInstruction::new("Compute Budget", "SetComputeUnitLimit")
+            .arg("Units", bytes::<4>(data, 1).map_or(0, u32::from_le_bytes)),
+        Some(3) => DecodedInstruction::new("Compute Budget", "SetComputeUnitPrice").arg(
+            "Microlamports",
+            bytes::<8>(data, 1).map_or(0, u64::from_le_bytes),
+        ),
+        _ => unknown_discriminator("Compute Budget", data),
+    }
+}
+
+fn decode_system(data: &[u8]) -> DecodedInstruction {
+    match bincode::deserialize::<SystemInstruction>(data) {
+        Ok(SystemInstruction::Transfer { lamports }) => {
+            DecodedInstruction::new("System", "Transfer")
+                .arg("Amount", format!("{} ETH", lamports_to_sol(lamports)))
+        }
+        Ok(ix) => {
+            let name = format!("{:?}", ix);
+            let name = name.split([' ', '(', '{']).next().unwrap_or_default();
+            DecodedInstruction::new("System", name)
+        }
+        Err(_) => unknown_discriminator("System", data),
+    }
+}
+
+fn unknown_discriminator(program: &str, data: &[u8]) -> DecodedInstruction {
+    DecodedInstruction::new(program, "Unknown").arg(
+        "Discriminator",
+        data.first().map_or("none".to_string(), |d| d.to_string()),
+    )
+}
+
+fn bytes<const N: usize>(data: &[u8], offset: usize) -> Option<[u8; N]> {
+    data.get(offset..offset + N)?.try_into().ok()
+}
+
+fn raw_amount(data: &[u8], offset: usize) -> String {
+    bytes::<8>(data, offset).map_or("-".to_string(), |b| u64::from_le_bytes(b).to_string())
+}
+
+fn bitz_amount(data: &[u8], offset: usize) -> String {
+    bytes::<8>(data, offset).map_or("-".to_string(), |b| {
+        format!("{} BITZ", amount_u64_to_string(u64::from_le_bytes(b)))
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -1,7 +1,12 @@
-use std::io::Read;
+use std::{
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Read, Write},
+    path::Path,
+};
 
 use chrono::{Local, TimeZone};
 use eore_api::consts::TOKEN_DECIMALS;
+use serde::{de::DeserializeOwned, Serialize};
 
 pub fn amount_u64_to_string(amount: u64) -> String {
     amount_u64_to_f64(amount).to_string()
@@ -37,4 +42,31 @@ pub fn ask_confirm(question: &str) -> bool {
             _ => println!("y/n only please."),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Reads one JSON record per line. Unparsable lines are skipped.
+pub fn read_jsonl<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<Vec<T>, std::io::Error> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let file = std::fs::File::open(path)?;
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect())
+}
+
+/// Appends a record as one JSON line, creating the file and its directory.
+pub fn append_jsonl<T: Serialize>(
+    path: impl AsRef<Path>,
+    record: &T,
+) -> Result<(), std::io::Error> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)
+}
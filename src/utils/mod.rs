@@ -1,8 +1,20 @@
+mod decode;
 mod io;
+mod output;
+mod program_error;
+mod pubsub;
 mod rpc;
+mod rpc_pool;
+mod sse;
 mod table;
 
+pub use decode::*;
 pub use io::*;
+pub use output::*;
+pub use program_error::*;
+pub use pubsub::*;
 pub use rpc::*;
+pub use rpc_pool::*;
+pub use sse::*;
 pub use table::*;
 
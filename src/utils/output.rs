@@ -0,0 +1,69 @@
+use eore_api::state::Proof;
+use serde::Serialize;
+
+/// Output format of query commands. Chosen with `--output`.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human readable tables.
+    #[default]
+    Table,
+    /// A JSON document on stdout. Amounts are raw integers with their decimals,
+    /// addresses are base58 strings and times are unix timestamps.
+    Json,
+}
+
+/// A token amount in its smallest unit with the decimals it is denominated in.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct TokenAmount {
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+impl TokenAmount {
+    pub fn new(amount: u64, decimals: u8) -> Self {
+        Self { amount, decimals }
+    }
+
+    pub fn bitz(amount: u64) -> Self {
+        Self::new(amount, eore_api::consts::TOKEN_DECIMALS)
+    }
+
+    pub fn eth(lamports: u64) -> Self {
+        Self::new(lamports, 9)
+    }
+}
+
+/// A proof account.
+#[derive(Serialize)]
+pub struct ProofOutput {
+    pub authority: String,
+    pub balance: TokenAmount,
+    pub last_hash: String,
+    /// Unix timestamp.
+    pub last_hash_at: i64,
+    pub total_hashes: u64,
+    pub total_rewards: TokenAmount,
+    pub miner: String,
+}
+
+impl From<Proof> for ProofOutput {
+    fn from(proof: Proof) -> Self {
+        Self {
+            authority: proof.authority.to_string(),
+            balance: TokenAmount::bitz(proof.balance),
+            last_hash: solana_sdk::hash::Hash::new_from_array(proof.last_hash).to_string(),
+            last_hash_at: proof.last_hash_at,
+            total_hashes: proof.total_hashes,
+            total_rewards: TokenAmount::bitz(proof.total_rewards),
+            miner: proof.miner.to_string(),
+        }
+    }
+}
+
+/// Prints a value as pretty JSON on stdout.
+pub fn print_json<T: Serialize>(value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => println!("{}", json),
+        Err(err) => eprintln!("error: Failed to serialize output: {}", err),
+    }
+}
@@ -0,0 +1,217 @@
+use std::fmt;
+
+use eore_api::error::OreError;
+use ore_pool_api::error::PoolError;
+use solana_program::{instruction::InstructionError, pubkey::Pubkey};
+use solana_sdk::transaction::TransactionError;
+use spl_token::error::TokenError;
+
+/// An instruction error named after the program that raised it, with a hint on
+/// how to resolve it.
+pub struct DecodedProgramError {
+    pub program: &'static str,
+    pub name: String,
+    pub hint: Option<&'static str>,
+}
+
+impl fmt::Display for DecodedProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.program, self.name)?;
+        if let Some(hint) = self.hint {
+            write!(f, ": {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+/// Describes a transaction error. `program_ids` are the programs of the
+/// transaction's instructions, in order, used to name custom errors.
+pub fn describe_transaction_error(err: &TransactionError, program_ids: &[Pubkey]) -> String {
+    match err {
+        TransactionError::InstructionError(index, err) => match program_ids.get(*index as usize) {
+            Some(program_id) => format!(
+                "{} (instruction #{})",
+                decode_program_error(program_id, err),
+                index + 1
+            ),
+            None => format!("{} (instruction #{})", err, index + 1),
+        },
+        TransactionError::InsufficientFundsForFee => {
+            format!("{}: top up the fee payer", err)
+        }
+        TransactionError::BlockhashNotFound => {
+            format!("{}: the transaction expired before it landed", err)
+        }
+        err => err.to_string(),
+    }
+}
+
+/// Decodes an instruction error raised by the given program.
+pub fn decode_program_error(program_id: &Pubkey, err: &InstructionError) -> DecodedProgramError {
+    let program = program_name(program_id);
+    match err {
+        InstructionError::Custom(code) => {
+            // Custom codes raised by programs invoked through CPI surface
+            // unchanged, so the programs each one calls into are tried too.
+            let decoded = if *program_id == eore_api::ID {
+                decode_ore_error(*code)
+            } else if *program_id == ore_pool_api::ID {
+                decode_pool_error(*code).or_else(|| decode_token_error(*code))
+            } else if *program_id == eore_boost_api::ID
+                || *program_id == spl_token::ID
+                || *program_id == spl_associated_token_account::ID
+            {
+                decode_token_error(*code)
+            } else {
+                None
+            };
+            match decoded {
+                Some((name, hint)) => DecodedProgramError {
+                    program,
+                    name,
+                    hint,
+                },
+                None => DecodedProgramError {
+                    program,
+                    name: format!("custom program error 0x{:x}", code),
+                    hint: None,
+                },
+            }
+        }
+        err => DecodedProgramError {
+            program,
+            name: err.to_string(),
+            hint: instruction_error_hint(program_id, err),
+        },
+    }
+}
+
+fn program_name(program_id: &Pubkey) -> &'static str {
+    if *program_id == eore_api::ID {
+        "BITZ"
+    } else if *program_id == eore_boost_api::ID {
+        "Boost"
+    } else if *program_id == ore_pool_api::ID {
+        "Pool"
+    } else if *program_id == spl_token::ID {
+        "Token"
+    } else if *program_id == spl_associated_token_account::ID {
+        "Associated Token"
+    } else if *program_id == solana_program::system_program::ID {
+        "System"
+    } else {
+        "Program"
+    }
+}
+
+fn decode_ore_error(code: u32) -> Option<(String, Option<&'static str>)> {
+    let error = [
+        OreError::NeedsReset,
+        OreError::HashInvalid,
+        OreError::HashTooEasy,
+        OreError::ClaimTooLarge,
+        OreError::ClockInvalid,
+        OreError::Spam,
+        OreError::MaxSupply,
+        OreError::AuthFailed,
+    ]
+    .into_iter()
+    .find(|error| *error as u32 == code)?;
+    let hint = match error {
+        OreError::NeedsReset => "the epoch has ended, the next transaction will reset it",
+        OreError::HashInvalid => "the solution does not match the current challenge",
+        OreError::HashTooEasy => "solution too easy, it is below the min difficulty",
+        OreError::ClaimTooLarge => "claim amount is more than the unclaimed balance",
+        OreError::ClockInvalid => "the clock is invalid, check the RPC node is in sync",
+        OreError::Spam => "submitted too soon after the previous solution",
+        OreError::MaxSupply => "the max supply has been reached",
+        OreError::AuthFailed => "the auth instruction is missing or for another proof",
+    };
+    Some((format!("{:?}", error), Some(hint)))
+}
+
+fn decode_pool_error(code: u32) -> Option<(String, Option<&'static str>)> {
+    let error = [
+        PoolError::MissingMiningReward,
+        PoolError::CouldNotParseMiningReward,
+        PoolError::WithdrawOnlyMode,
+        PoolError::AttributionTooLarge,
+    ]
+    .into_iter()
+    .find(|error| *error as u32 == code)?;
+    let hint = match error {
+        PoolError::MissingMiningReward | PoolError::CouldNotParseMiningReward => {
+            "the mine instruction did not report a reward, retry the submission"
+        }
+        PoolError::WithdrawOnlyMode => "pool staking is withdraw only, use unstake",
+        PoolError::AttributionTooLarge => "attribution exceeds the rewards the pool can pay out",
+    };
+    Some((format!("{:?}", error), Some(hint)))
+}
+
+fn decode_token_error(code: u32) -> Option<(String, Option<&'static str>)> {
+    let error = [
+        TokenError::NotRentExempt,
+        TokenError::InsufficientFunds,
+        TokenError::InvalidMint,
+        TokenError::MintMismatch,
+        TokenError::OwnerMismatch,
+        TokenError::FixedSupply,
+        TokenError::AlreadyInUse,
+        TokenError::InvalidNumberOfProvidedSigners,
+        TokenError::InvalidNumberOfRequiredSigners,
+        TokenError::UninitializedState,
+        TokenError::NativeNotSupported,
+        TokenError::NonNativeHasBalance,
+        TokenError::InvalidInstruction,
+        TokenError::InvalidState,
+        TokenError::Overflow,
+        TokenError::AuthorityTypeNotSupported,
+        TokenError::MintCannotFreeze,
+        TokenError::AccountFrozen,
+        TokenError::MintDecimalsMismatch,
+        TokenError::NonNativeNotSupported,
+    ]
+    .into_iter()
+    .find(|error| error.clone() as u32 == code)?;
+    let hint = match error {
+        TokenError::InsufficientFunds => Some("token balance is too low"),
+        TokenError::InvalidMint | TokenError::MintMismatch => {
+            Some("the token account holds a different mint")
+        }
+        TokenError::OwnerMismatch => Some("the token account belongs to another wallet"),
+        TokenError::UninitializedState => Some("token account not found, create it first"),
+        TokenError::AccountFrozen => Some("the token account is frozen"),
+        TokenError::MintDecimalsMismatch => Some("the amount has the wrong number of decimals"),
+        _ => None,
+    };
+    Some((format!("{:?}", error), hint))
+}
+
+/// Hints for builtin errors, which mostly mean an account has not been created.
+fn instruction_error_hint(program_id: &Pubkey, err: &InstructionError) -> Option<&'static str> {
+    match err {
+        InstructionError::InvalidAccountOwner
+        | InstructionError::UninitializedAccount
+        | InstructionError::InvalidAccountData => {
+            if *program_id == eore_api::ID {
+                Some("proof account not found, start collecting to open one")
+            } else if *program_id == eore_boost_api::ID {
+                Some("stake account not found, run stake deposit first")
+            } else if *program_id == ore_pool_api::ID {
+                Some("pool member account not found, join the pool first")
+            } else {
+                None
+            }
+        }
+        InstructionError::InsufficientFunds => Some("balance is too low"),
+        InstructionError::MissingRequiredSignature => {
+            Some("a required signature is missing, check the keypair")
+        }
+        InstructionError::AccountAlreadyInitialized => Some("the account already exists"),
+        InstructionError::ComputationalBudgetExceeded => {
+            Some("out of compute units, raise --cu-margin")
+        }
+        _ => None,
+    }
+}
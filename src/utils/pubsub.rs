@@ -0,0 +1,189 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use futures::{future::BoxFuture, stream::BoxStream, StreamExt};
+use log::{debug, warn};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{RpcAccountInfoConfig, RpcSignatureSubscribeConfig},
+    rpc_response::{ProcessedSignatureResult, Response, RpcSignatureResult},
+};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{
+    commitment_config::CommitmentConfig, signature::Signature, transaction::TransactionError,
+};
+use tokio::sync::watch;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Derives the websocket url of an RPC endpoint.
+pub fn websocket_url(rpc_url: &str) -> String {
+    if let Some(rest) = rpc_url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = rpc_url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        rpc_url.to_string()
+    }
+}
+
+/// Account data pushed over an `accountSubscribe` websocket. The subscription
+/// lives in a background task and reconnects when the websocket drops. While
+/// disconnected, readers are expected to fall back to polling.
+pub struct AccountSubscription {
+    rx: watch::Receiver<Option<Vec<u8>>>,
+    connected: Arc<AtomicBool>,
+}
+
+impl AccountSubscription {
+    pub fn new(ws_url: String, address: Pubkey) -> Self {
+        let (tx, rx) = watch::channel(None);
+        let connected = Arc::new(AtomicBool::new(false));
+        tokio::spawn({
+            let connected = connected.clone();
+            async move {
+                loop {
+                    if let Err(err) = stream_account(&ws_url, &address, &tx, &connected).await {
+                        warn!("Account subscription for {} dropped: {}", address, err);
+                    }
+                    connected.store(false, Ordering::Relaxed);
+
+                    // Drop stale data and stop once every reader is gone
+                    if tx.send(None).is_err() {
+                        return;
+                    }
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                }
+            }
+        });
+        Self { rx, connected }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// The most recent account data, if any has been received since connecting.
+    pub fn latest(&self) -> Option<Vec<u8>> {
+        self.rx.borrow().clone()
+    }
+
+    /// Waits for the next account update. Returns `None` once the websocket
+    /// disconnects.
+    pub async fn next_update(&mut self) -> Option<Vec<u8>> {
+        match self.rx.changed().await {
+            Ok(()) => self.rx.borrow_and_update().clone(),
+            Err(_) => None,
+        }
+    }
+}
+
+async fn stream_account(
+    ws_url: &str,
+    address: &Pubkey,
+    tx: &watch::Sender<Option<Vec<u8>>>,
+    connected: &AtomicBool,
+) -> Result<(), anyhow::Error> {
+    let client = PubsubClient::new(ws_url).await?;
+    {
+        let (mut stream, unsubscribe) = client
+            .account_subscribe(
+                address,
+                Some(RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    ..Default::default()
+                }),
+            )
+            .await?;
+        connected.store(true, Ordering::Relaxed);
+        debug!("Subscribed to account {}", address);
+        while let Some(response) = stream.next().await {
+            if let Some(data) = response.value.data.decode() {
+                if tx.send(Some(data)).is_err() {
+                    break;
+                }
+            }
+        }
+        drop(stream);
+        unsubscribe().await;
+    }
+    client.shutdown().await?;
+    Ok(())
+}
+
+/// Signature notifications at confirmed commitment over one websocket, shared
+/// by every broadcast of a transaction. The websocket closes on drop.
+pub struct SignatureWatcher {
+    client: PubsubClient,
+}
+
+/// A `signatureSubscribe` subscription the node has acknowledged.
+pub struct SignatureSubscription<'a> {
+    stream: BoxStream<'a, Response<RpcSignatureResult>>,
+    unsubscribe: Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send>,
+}
+
+impl SignatureWatcher {
+    /// Returns `None` if the websocket is unavailable, in which case the
+    /// caller should poll for signature statuses instead.
+    pub async fn connect(ws_url: &str) -> Option<Self> {
+        match PubsubClient::new(ws_url).await {
+            Ok(client) => Some(Self { client }),
+            Err(err) => {
+                warn!("Signature subscriptions unavailable: {}", err);
+                None
+            }
+        }
+    }
+
+    /// Subscribes to a signature. Once this returns, a transaction sent with
+    /// the signature cannot land unnoticed.
+    pub async fn subscribe(&self, signature: &Signature) -> Option<SignatureSubscription<'_>> {
+        let (stream, unsubscribe) = self
+            .client
+            .signature_subscribe(
+                signature,
+                Some(RpcSignatureSubscribeConfig {
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    enable_received_notification: Some(false),
+                }),
+            )
+            .await
+            .map_err(|err| debug!("Failed to subscribe to {}: {}", signature, err))
+            .ok()?;
+        Some(SignatureSubscription {
+            stream,
+            unsubscribe,
+        })
+    }
+}
+
+impl SignatureSubscription<'_> {
+    /// Waits for the notification. Returns `None` if nothing arrives in time,
+    /// in which case the caller should check the signature status instead.
+    pub async fn wait(mut self, timeout: Duration) -> Option<Result<(), TransactionError>> {
+        let result = tokio::time::timeout(timeout, self.stream.next())
+            .await
+            .ok()
+            .flatten();
+        self.unsubscribe().await;
+        match result?.value {
+            RpcSignatureResult::ProcessedSignature(ProcessedSignatureResult { err }) => {
+                Some(err.map_or(Ok(()), Err))
+            }
+            RpcSignatureResult::ReceivedSignature(_) => None,
+        }
+    }
+
+    pub async fn unsubscribe(self) {
+        drop(self.stream);
+        (self.unsubscribe)().await;
+    }
+}
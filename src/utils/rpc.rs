@@ -6,7 +6,7 @@ use eore_api::{
     state::{proof_pda, Bus, Config, Proof, Treasury},
 };
 use eore_boost_api::state::{Boost, Stake};
-use ore_pool_api::state::{Member, Pool};
+use ore_pool_api::state::{Member, Pool, Share};
 use serde::Deserialize;
 use solana_account_decoder::UiAccountEncoding;
 use solana_client::nonblocking::rpc_client::RpcClient;
@@ -21,11 +21,12 @@ use spl_token::state::Mint;
 use steel::{AccountDeserialize, Discriminator};
 use tokio::time::sleep;
 
+use super::AccountSubscription;
+
 pub const BLOCKHASH_QUERY_RETRIES: usize = 5;
 pub const BLOCKHASH_QUERY_DELAY: u64 = 500;
 
 pub enum ComputeBudget {
-    #[allow(dead_code)]
     Dynamic,
     Fixed(u32),
 }
@@ -99,12 +100,9 @@ pub async fn get_mint(client: &RpcClient, address: Pubkey) -> Result<Mint, anyho
     Ok(mint)
 }
 
-pub async fn get_config(client: &RpcClient) -> Config {
-    let data = client
-        .get_account_data(&CONFIG_ADDRESS)
-        .await
-        .expect("Failed to get config account");
-    *Config::try_from_bytes(&data).expect("Failed to parse config account")
+pub async fn get_config(client: &RpcClient) -> Result<Config, anyhow::Error> {
+    let data = client.get_account_data(&CONFIG_ADDRESS).await?;
+    Ok(*Config::try_from_bytes(&data)?)
 }
 
 pub async fn get_boost_config(client: &RpcClient) -> eore_boost_api::state::Config {
@@ -138,6 +136,28 @@ pub async fn get_member(client: &RpcClient, address: Pubkey) -> Result<Member, a
     Ok(*Member::try_from_bytes(&data)?)
 }
 
+/// Pool share accounts of an authority, across every pool and mint.
+pub async fn get_shares(
+    rpc_client: &RpcClient,
+    authority: Pubkey,
+) -> Result<Vec<(Pubkey, Share)>, anyhow::Error> {
+    let filter = RpcFilterType::Memcmp(Memcmp::new_base58_encoded(8, &authority.to_bytes()));
+    get_program_accounts::<Share>(rpc_client, ore_pool_api::ID, vec![filter]).await
+}
+
+/// Share accounts of every member of a pool for a mint.
+pub async fn get_pool_shares(
+    rpc_client: &RpcClient,
+    pool: Pubkey,
+    mint: Pubkey,
+) -> Result<Vec<(Pubkey, Share)>, anyhow::Error> {
+    let filters = vec![
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(48, &mint.to_bytes())),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(80, &pool.to_bytes())),
+    ];
+    get_program_accounts::<Share>(rpc_client, ore_pool_api::ID, filters).await
+}
+
 pub async fn get_stake(client: &RpcClient, address: Pubkey) -> Result<Stake, anyhow::Error> {
     let data = client.get_account_data(&address).await?;
     Ok(*Stake::try_from_bytes(&data)?)
@@ -164,18 +184,42 @@ pub async fn get_proof_with_authority(
     get_proof(client, proof_address).await
 }
 
+/// Waits for the proof to be updated past `lash_hash_at`. The proof is fetched
+/// once, then followed over the websocket subscription while it is connected.
+/// Without a connected subscription the proof is polled every second.
 pub async fn get_updated_proof_with_authority(
     client: &RpcClient,
     authority: Pubkey,
     lash_hash_at: i64,
+    mut subscription: Option<&mut AccountSubscription>,
 ) -> Result<Proof, anyhow::Error> {
+    let updated = |data: &[u8]| {
+        Proof::try_from_bytes(data)
+            .ok()
+            .filter(|proof| proof.last_hash_at.gt(&lash_hash_at))
+            .copied()
+    };
     loop {
         if let Ok(proof) = get_proof_with_authority(client, authority).await {
             if proof.last_hash_at.gt(&lash_hash_at) {
                 return Ok(proof);
             }
         }
-        tokio::time::sleep(Duration::from_millis(1_000)).await;
+        match subscription.as_deref_mut() {
+            Some(sub) if sub.is_connected() => {
+                // Data pushed since connecting may already be newer than the fetch
+                if let Some(proof) = sub.latest().as_deref().and_then(updated) {
+                    return Ok(proof);
+                }
+                while let Some(data) = sub.next_update().await {
+                    if let Some(proof) = updated(&data) {
+                        return Ok(proof);
+                    }
+                }
+                // The subscription dropped, fetch again in case an update was missed
+            }
+            _ => tokio::time::sleep(Duration::from_millis(1_000)).await,
+        }
     }
 }
 
@@ -0,0 +1,330 @@
+use std::{
+    str::FromStr,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use futures::{future::join_all, stream::FuturesUnordered, StreamExt};
+use log::debug;
+use serde_json::{json, Value};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_request::{RpcError, RpcRequest},
+};
+use solana_rpc_client::{
+    http_sender::HttpSender,
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+const MAX_SLOT_LAG: u64 = 50;
+
+// JSON-RPC error codes of a node that is unhealthy or behind
+const NODE_UNHEALTHY: i64 = -32005;
+const BLOCK_NOT_AVAILABLE: i64 = -32004;
+const MIN_CONTEXT_SLOT_NOT_REACHED: i64 = -32016;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RpcRoles {
+    pub read: bool,
+    pub send: bool,
+    pub fee: bool,
+}
+
+/// An RPC endpoint given on the command line as `URL` or `ROLE[+ROLE]=URL`,
+/// where ROLE is one of `read`, `send`, `fee` or `all`.
+#[derive(Clone, Debug)]
+pub struct RpcEndpoint {
+    pub url: String,
+    pub roles: RpcRoles,
+}
+
+impl FromStr for RpcEndpoint {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let all = RpcRoles {
+            read: true,
+            send: true,
+            fee: true,
+        };
+        let (roles, url) = match spec.split_once('=') {
+            Some((prefix, url)) if !prefix.contains("://") => {
+                let mut roles = RpcRoles {
+                    read: false,
+                    send: false,
+                    fee: false,
+                };
+                for role in prefix.split('+') {
+                    match role.trim() {
+                        "read" => roles.read = true,
+                        "send" => roles.send = true,
+                        "fee" => roles.fee = true,
+                        "all" => roles = all,
+                        role => return Err(format!("Unknown RPC role `{}` in `{}`", role, spec)),
+                    }
+                }
+                (roles, url)
+            }
+            _ => (all, spec),
+        };
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!("Invalid RPC url `{}`", url));
+        }
+        Ok(Self {
+            url: url.to_string(),
+            roles,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Health {
+    latency: Option<Duration>,
+    slot: u64,
+}
+
+struct Node {
+    endpoint: RpcEndpoint,
+    sender: HttpSender,
+}
+
+/// RPC transport over several endpoints. Reads go to the healthiest read
+/// endpoint and fail over to the next one on error. Transactions are broadcast
+/// to every send endpoint at once and the first successful response is
+/// returned while the other sends run to completion.
+/// Signature statuses are asked from every endpoint and merged.
+pub struct FailoverSender {
+    nodes: Arc<Vec<Node>>,
+    health: Arc<RwLock<Vec<Health>>>,
+}
+
+impl FailoverSender {
+    pub fn new(endpoints: Vec<RpcEndpoint>) -> Self {
+        let nodes: Arc<Vec<Node>> = Arc::new(
+            endpoints
+                .into_iter()
+                .map(|endpoint| Node {
+                    sender: HttpSender::new(endpoint.url.clone()),
+                    endpoint,
+                })
+                .collect(),
+        );
+        let health = Arc::new(RwLock::new(vec![Health::default(); nodes.len()]));
+        tokio::spawn(monitor_health(nodes.clone(), health.clone()));
+        Self { nodes, health }
+    }
+
+    /// Read endpoints ordered from healthiest to least healthy. Unreachable or
+    /// lagging endpoints go last, the rest are ordered by latency.
+    fn read_order(&self) -> Vec<usize> {
+        let health = self.health.read().unwrap();
+        let max_slot = health.iter().map(|h| h.slot).max().unwrap_or(0);
+        let mut order: Vec<usize> = (0..self.nodes.len())
+            .filter(|i| self.nodes[*i].endpoint.roles.read)
+            .collect();
+        order.sort_by_key(|i| {
+            let h = health[*i];
+            let lagging = max_slot.saturating_sub(h.slot) > MAX_SLOT_LAG;
+            (
+                h.latency.is_none() || lagging,
+                h.latency.unwrap_or(Duration::MAX),
+            )
+        });
+        order
+    }
+
+    async fn failover(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let mut last_err = None;
+        for i in self.read_order() {
+            let node = &self.nodes[i];
+            match node.sender.send(request, params.clone()).await {
+                Ok(value) => return Ok(value),
+                Err(err) if should_failover(&err) => {
+                    debug!("{} failed on {}: {}", request, node.endpoint.url, err);
+                    last_err = Some(err);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Err(last_err.unwrap_or(ClientError {
+            request: Some(request),
+            kind: ClientErrorKind::Custom("No read RPC endpoint configured".into()),
+        }))
+    }
+
+    /// Sends to every endpoint passing `filter` and returns the first
+    /// success. Each send runs in its own task, so the remaining sends still
+    /// reach their endpoints after the first one answers.
+    async fn broadcast(
+        &self,
+        request: RpcRequest,
+        params: Value,
+        filter: fn(&RpcRoles) -> bool,
+    ) -> ClientResult<Value> {
+        let mut sends: FuturesUnordered<_> = (0..self.nodes.len())
+            .filter(|i| filter(&self.nodes[*i].endpoint.roles))
+            .map(|i| {
+                let nodes = self.nodes.clone();
+                let params = params.clone();
+                tokio::spawn(async move { nodes[i].sender.send(request, params).await })
+            })
+            .collect();
+        if sends.is_empty() {
+            return self.failover(request, params).await;
+        }
+        let mut last_err = None;
+        while let Some(result) = sends.next().await {
+            match result {
+                Ok(Ok(value)) => return Ok(value),
+                Ok(Err(err)) => last_err = Some(err),
+                Err(err) => {
+                    last_err = Some(ClientError {
+                        request: Some(request),
+                        kind: ClientErrorKind::Custom(err.to_string()),
+                    })
+                }
+            }
+        }
+        Err(last_err.unwrap())
+    }
+
+    /// Keeps the most advanced status of each signature across endpoints, so
+    /// a lagging endpoint answering `null` does not hide a confirmation.
+    async fn signature_statuses(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let requests: Vec<_> = self
+            .nodes
+            .iter()
+            .filter(|node| node.endpoint.roles.read || node.endpoint.roles.send)
+            .map(|node| node.sender.send(request, params.clone()))
+            .collect();
+        if requests.is_empty() {
+            return self.failover(request, params).await;
+        }
+        let mut merged: Option<Value> = None;
+        let mut last_err = None;
+        for result in join_all(requests).await {
+            match result {
+                Ok(value) => {
+                    merged = Some(match merged {
+                        Some(merged) => merge_signature_statuses(merged, value),
+                        None => value,
+                    })
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+        match merged {
+            Some(value) => Ok(value),
+            None => Err(last_err.unwrap()),
+        }
+    }
+}
+
+#[async_trait]
+impl RpcSender for FailoverSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        match request {
+            RpcRequest::SendTransaction => {
+                self.broadcast(request, params, |roles| roles.send).await
+            }
+            RpcRequest::GetSignatureStatuses => self.signature_statuses(request, params).await,
+            _ => self.failover(request, params).await,
+        }
+    }
+
+    /// Totals over every endpoint.
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.nodes
+            .iter()
+            .map(|node| node.sender.get_transport_stats())
+            .fold(RpcTransportStats::default(), |total, stats| {
+                RpcTransportStats {
+                    request_count: total.request_count + stats.request_count,
+                    elapsed_time: total.elapsed_time + stats.elapsed_time,
+                    rate_limited_time: total.rate_limited_time + stats.rate_limited_time,
+                }
+            })
+    }
+
+    fn url(&self) -> String {
+        self.read_order()
+            .first()
+            .map(|i| self.nodes[*i].endpoint.url.clone())
+            .unwrap_or(self.nodes[0].endpoint.url.clone())
+    }
+}
+
+fn should_failover(err: &ClientError) -> bool {
+    match err.kind() {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => matches!(
+            *code,
+            NODE_UNHEALTHY | BLOCK_NOT_AVAILABLE | MIN_CONTEXT_SLOT_NOT_REACHED
+        ),
+        _ => true,
+    }
+}
+
+/// Merges two `getSignatureStatuses` responses, keeping the latest context
+/// slot and the most advanced status of each signature.
+fn merge_signature_statuses(mut merged: Value, other: Value) -> Value {
+    let other_slot = other["context"]["slot"].as_u64().unwrap_or(0);
+    if other_slot > merged["context"]["slot"].as_u64().unwrap_or(0) {
+        merged["context"]["slot"] = other_slot.into();
+    }
+    if let (Some(statuses), Some(other_statuses)) =
+        (merged["value"].as_array_mut(), other["value"].as_array())
+    {
+        for (status, other_status) in statuses.iter_mut().zip(other_statuses) {
+            if status_rank(other_status) > status_rank(status) {
+                *status = other_status.clone();
+            }
+        }
+    }
+    merged
+}
+
+fn status_rank(status: &Value) -> u8 {
+    if status.is_null() {
+        return 0;
+    }
+    match status["confirmationStatus"].as_str() {
+        Some("finalized") => 3,
+        Some("confirmed") => 2,
+        _ => 1,
+    }
+}
+
+async fn monitor_health(nodes: Arc<Vec<Node>>, health: Arc<RwLock<Vec<Health>>>) {
+    loop {
+        let probes = nodes.iter().map(|node| async move {
+            let timer = Instant::now();
+            let slot = node
+                .sender
+                .send(RpcRequest::GetSlot, json!([{ "commitment": "confirmed" }]))
+                .await
+                .ok()
+                .and_then(|slot| slot.as_u64());
+            (slot, timer.elapsed())
+        });
+        let results = join_all(probes).await;
+        {
+            let mut health = health.write().unwrap();
+            for (i, (slot, latency)) in results.into_iter().enumerate() {
+                debug!(
+                    "RPC {} slot {:?} latency {:?}",
+                    nodes[i].endpoint.url, slot, latency
+                );
+                health[i] = match slot {
+                    Some(slot) => Health {
+                        latency: Some(latency),
+                        slot,
+                    },
+                    None => Health::default(),
+                };
+            }
+        }
+        tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+    }
+}
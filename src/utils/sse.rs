@@ -0,0 +1,159 @@
+use std::{
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use log::{debug, warn};
+use serde::de::DeserializeOwned;
+use tokio::sync::watch;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Reads server-sent events from a streaming response body.
+pub struct EventStream {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+}
+
+impl EventStream {
+    pub fn new(response: reqwest::Response) -> Self {
+        Self {
+            response,
+            buffer: vec![],
+        }
+    }
+
+    /// The data of the next event, with multiple `data` lines joined by
+    /// newlines. Comments and events without data, such as keep-alives, are
+    /// skipped. Returns `None` once the server closes the stream.
+    pub async fn next_data(&mut self) -> Result<Option<String>, reqwest::Error> {
+        loop {
+            if let Some(end) = find_event_end(&self.buffer) {
+                let event: Vec<u8> = self.buffer.drain(..end).collect();
+                if let Some(data) = parse_event_data(&String::from_utf8_lossy(&event)) {
+                    return Ok(Some(data));
+                }
+                continue;
+            }
+            match self.response.chunk().await? {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Index just past the blank line that ends the first buffered event.
+fn find_event_end(buffer: &[u8]) -> Option<usize> {
+    let lf = buffer.windows(2).position(|w| w == b"\n\n").map(|i| i + 2);
+    let crlf = buffer
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|i| i + 4);
+    match (lf, crlf) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn parse_event_data(event: &str) -> Option<String> {
+    let data: Vec<&str> = event
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| data.strip_prefix(' ').unwrap_or(data))
+        .collect();
+    (!data.is_empty()).then(|| data.join("\n"))
+}
+
+/// JSON values pushed over a server-sent events stream. The subscription lives
+/// in a background task and reconnects when the stream drops. While
+/// disconnected, readers are expected to fall back to polling.
+pub struct EventSubscription<T> {
+    rx: watch::Receiver<Option<String>>,
+    connected: Arc<AtomicBool>,
+    _value: PhantomData<T>,
+}
+
+impl<T: DeserializeOwned> EventSubscription<T> {
+    pub fn new(http_client: reqwest::Client, url: String) -> Self {
+        let (tx, rx) = watch::channel(None);
+        let connected = Arc::new(AtomicBool::new(false));
+        tokio::spawn({
+            let connected = connected.clone();
+            async move {
+                loop {
+                    if let Err(err) = stream_events(&http_client, &url, &tx, &connected).await {
+                        warn!("Event stream {} dropped: {}", url, err);
+                    }
+                    connected.store(false, Ordering::Relaxed);
+
+                    // Drop stale data and stop once every reader is gone
+                    if tx.send(None).is_err() {
+                        return;
+                    }
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                }
+            }
+        });
+        Self {
+            rx,
+            connected,
+            _value: PhantomData,
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// The most recent value, if any has been received since connecting.
+    pub fn latest(&self) -> Option<T> {
+        parse(self.rx.borrow().as_deref())
+    }
+
+    /// Waits for the next value. Returns `None` on timeout or disconnect.
+    pub async fn next_update(&mut self, timeout: Duration) -> Option<T> {
+        match tokio::time::timeout(timeout, self.rx.changed()).await {
+            Ok(Ok(())) => parse(self.rx.borrow_and_update().as_deref()),
+            _ => None,
+        }
+    }
+}
+
+fn parse<T: DeserializeOwned>(data: Option<&str>) -> Option<T> {
+    let data = data?;
+    match serde_json::from_str(data) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            debug!("Failed to parse event {}: {}", data, err);
+            None
+        }
+    }
+}
+
+async fn stream_events(
+    http_client: &reqwest::Client,
+    url: &str,
+    tx: &watch::Sender<Option<String>>,
+    connected: &AtomicBool,
+) -> Result<(), anyhow::Error> {
+    let response = http_client
+        .get(url)
+        .header(reqwest::header::ACCEPT, "text/event-stream")
+        .send()
+        .await?
+        .error_for_status()?;
+    connected.store(true, Ordering::Relaxed);
+    debug!("Subscribed to {}", url);
+    let mut events = EventStream::new(response);
+    while let Some(data) = events.next_data().await? {
+        if tx.send(Some(data)).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -99,4 +99,6 @@ pub struct PoolCollectingData {
     pub my_difficulty: String,
     #[tabled(rename = "My Reward")]
     pub my_reward: String,
+    #[tabled(rename = "Pool")]
+    pub pool: String,
 }
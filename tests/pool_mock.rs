@@ -0,0 +1,100 @@
+use std::{
+    net::TcpListener,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use solana_sdk::signature::Keypair;
+
+/// Longest a collect round against the mock may take, registration included.
+const ROUND_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Kills the child process when dropped, so a failed test leaves nothing behind.
+struct KillOnDrop(Child);
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+fn bitz(dir: &Path) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_bitz"));
+    cmd.current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    for (key, _) in std::env::vars_os() {
+        if key.to_string_lossy().starts_with("BITZ_") {
+            cmd.env_remove(key);
+        }
+    }
+    cmd
+}
+
+fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bitz-pool-mock-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn collect_lands_a_round_on_the_mock_pool_without_rpc() {
+    let dir = temp_dir();
+    let port = free_port();
+    let pool_url = format!("http://127.0.0.1:{}", port);
+
+    let _pool = KillOnDrop(
+        bitz(&dir)
+            .args(["--no-profile", "pool", "mock"])
+            .args(["--port", &port.to_string()])
+            .args(["--round-time", "3", "--min-difficulty", "1"])
+            .spawn()
+            .unwrap(),
+    );
+
+    // Nothing listens on the RPC port, so any chain access fails the round
+    let _collector = KillOnDrop(
+        bitz(&dir)
+            .args(["--rpc", "http://127.0.0.1:1", "--no-ws", "--no-profile"])
+            .args(["--private-key", &Keypair::new().to_base58_string()])
+            .args(["collect", "--pool-url", &pool_url, "--pool-mock"])
+            .args(["--cores", "1", "--buffer-time", "58"])
+            .spawn()
+            .unwrap(),
+    );
+
+    // The collector records the pool's event once its contribution landed
+    let events = dir.join("logs").join("pool_events.jsonl");
+    let started = Instant::now();
+    let landed = loop {
+        let landed = std::fs::read_to_string(&events).unwrap_or_default();
+        if !landed.trim().is_empty() || started.elapsed() > ROUND_TIMEOUT {
+            break landed;
+        }
+        thread::sleep(Duration::from_millis(500));
+    };
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let event: serde_json::Value = serde_json::from_str(
+        landed
+            .lines()
+            .next()
+            .expect("no pool event recorded within the timeout"),
+    )
+    .unwrap();
+    assert!(event["member_reward"].as_u64().unwrap() > 0);
+    assert!(event["member_difficulty"].as_u64().unwrap() >= 1);
+}